@@ -0,0 +1,205 @@
+use std::{cmp::Ordering, fmt};
+
+/** just enough unsigned big integer for worry levels that outgrow u128 */
+#[derive(Debug, Clone, Default, PartialEq, Eq, Hash)]
+pub struct BigUint {
+    /** little endian, never ends in a zero limb so zero is empty */
+    limbs: Vec<u32>,
+}
+
+impl BigUint {
+    pub fn is_zero(&self) -> bool {
+        self.limbs.is_empty()
+    }
+
+    fn trim(mut self) -> Self {
+        while self.limbs.last() == Some(&0) {
+            self.limbs.pop();
+        }
+        self
+    }
+
+    fn bits(&self) -> usize {
+        match self.limbs.last() {
+            Some(top) => self.limbs.len() * 32 - top.leading_zeros() as usize,
+            None => 0,
+        }
+    }
+
+    fn bit(&self, index: usize) -> bool {
+        self.limbs
+            .get(index / 32)
+            .is_some_and(|limb| limb >> (index % 32) & 1 == 1)
+    }
+
+    pub fn add(&self, other: &Self) -> Self {
+        let mut limbs = Vec::with_capacity(self.limbs.len().max(other.limbs.len()) + 1);
+        let mut carry = 0u64;
+        for i in 0..self.limbs.len().max(other.limbs.len()) {
+            let sum = carry
+                + *self.limbs.get(i).unwrap_or(&0) as u64
+                + *other.limbs.get(i).unwrap_or(&0) as u64;
+            limbs.push(sum as u32);
+            carry = sum >> 32;
+        }
+        limbs.push(carry as u32);
+        Self { limbs }.trim()
+    }
+
+    /** None when `other` is bigger */
+    pub fn checked_sub(&self, other: &Self) -> Option<Self> {
+        if self < other {
+            return None;
+        }
+        let mut limbs = Vec::with_capacity(self.limbs.len());
+        let mut borrow = 0i64;
+        for (i, limb) in self.limbs.iter().enumerate() {
+            let mut difference = *limb as i64 - *other.limbs.get(i).unwrap_or(&0) as i64 - borrow;
+            borrow = 0;
+            if difference < 0 {
+                difference += 1 << 32;
+                borrow = 1;
+            }
+            limbs.push(difference as u32);
+        }
+        Some(Self { limbs }.trim())
+    }
+
+    pub fn mul(&self, other: &Self) -> Self {
+        let mut limbs = vec![0u32; self.limbs.len() + other.limbs.len()];
+        for (i, a) in self.limbs.iter().enumerate() {
+            let mut carry = 0u64;
+            for (j, b) in other.limbs.iter().enumerate() {
+                let product = *a as u64 * *b as u64 + limbs[i + j] as u64 + carry;
+                limbs[i + j] = product as u32;
+                carry = product >> 32;
+            }
+            limbs[i + other.limbs.len()] = carry as u32;
+        }
+        Self { limbs }.trim()
+    }
+
+    /** quotient and remainder, None when dividing by zero */
+    pub fn div_rem_u64(&self, divisor: u64) -> Option<(Self, u64)> {
+        if divisor == 0 {
+            return None;
+        }
+        let mut limbs = vec![0u32; self.limbs.len()];
+        let mut remainder = 0u128;
+        for (i, limb) in self.limbs.iter().enumerate().rev() {
+            let current = remainder << 32 | *limb as u128;
+            limbs[i] = (current / divisor as u128) as u32;
+            remainder = current % divisor as u128;
+        }
+        Some((Self { limbs }.trim(), remainder as u64))
+    }
+
+    /** None when dividing by zero */
+    pub fn checked_div(&self, other: &Self) -> Option<Self> {
+        if let Ok(small) = u64::try_from(other) {
+            return self.div_rem_u64(small).map(|(quotient, _)| quotient);
+        }
+        // shift and subtract, one bit at a time
+        let mut quotient = vec![0u32; self.limbs.len()];
+        let mut remainder = BigUint::default();
+        for index in (0..self.bits()).rev() {
+            remainder = remainder.add(&remainder);
+            if self.bit(index) {
+                remainder = remainder.add(&BigUint::from(1));
+            }
+            if let Some(rest) = remainder.checked_sub(other) {
+                remainder = rest;
+                quotient[index / 32] |= 1 << (index % 32);
+            }
+        }
+        Some(Self { limbs: quotient }.trim())
+    }
+}
+
+impl From<u64> for BigUint {
+    fn from(value: u64) -> Self {
+        Self {
+            limbs: vec![value as u32, (value >> 32) as u32],
+        }
+        .trim()
+    }
+}
+
+impl TryFrom<&BigUint> for u64 {
+    type Error = ();
+
+    fn try_from(value: &BigUint) -> Result<Self, Self::Error> {
+        match value.limbs.as_slice() {
+            [] => Ok(0),
+            [low] => Ok(*low as u64),
+            [low, high] => Ok((*high as u64) << 32 | *low as u64),
+            _ => Err(()),
+        }
+    }
+}
+
+impl Ord for BigUint {
+    fn cmp(&self, other: &Self) -> Ordering {
+        self.limbs
+            .len()
+            .cmp(&other.limbs.len())
+            .then_with(|| self.limbs.iter().rev().cmp(other.limbs.iter().rev()))
+    }
+}
+
+impl PartialOrd for BigUint {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl fmt::Display for BigUint {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        const CHUNK: u64 = 1_000_000_000;
+        // peel off 9 decimal digits at a time, least significant first
+        let mut chunks = Vec::new();
+        let mut rest = self.clone();
+        while !rest.is_zero() {
+            let (quotient, remainder) = rest.div_rem_u64(CHUNK).unwrap();
+            chunks.push(remainder);
+            rest = quotient;
+        }
+        match chunks.pop() {
+            None => write!(f, "0"),
+            Some(top) => {
+                write!(f, "{}", top)?;
+                chunks
+                    .iter()
+                    .rev()
+                    .try_for_each(|chunk| write!(f, "{:09}", chunk))
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_arithmetic() {
+        let a = BigUint::from(u64::MAX);
+        let square = a.mul(&a);
+        assert_eq!(
+            square.to_string(),
+            (u64::MAX as u128 * u64::MAX as u128).to_string()
+        );
+        assert_eq!(square.checked_div(&a), Some(a.clone()));
+        assert_eq!(square.add(&a).checked_sub(&square), Some(a.clone()));
+        assert_eq!(a.checked_sub(&square), None);
+
+        let cube = square.mul(&a);
+        assert_eq!(cube.checked_div(&square), Some(a.clone()));
+        assert_eq!(cube.add(&BigUint::from(5)).div_rem_u64(7).unwrap().1, {
+            let m = u64::MAX as u128 % 7;
+            ((m * m % 7 * m + 5) % 7) as u64
+        });
+        assert_eq!(a.checked_div(&BigUint::default()), None);
+        assert_eq!(BigUint::default().to_string(), "0");
+    }
+}
@@ -1,12 +1,19 @@
+use crate::error::ParseError;
+
+const DAY: u8 = 1;
+
 type Input = Vec<Vec<u32>>;
 
-pub fn generator(input: &str) -> Input {
+pub fn generator(input: &str) -> Result<Input, ParseError> {
     input
         .split("\n\n")
         .map(|chunk| {
             chunk
                 .lines()
-                .map(|line| line.parse::<u32>().unwrap())
+                .map(|line| {
+                    line.parse::<u32>()
+                        .map_err(|_| ParseError::new(DAY, input, line, "expected calories"))
+                })
                 .collect()
         })
         .collect()
@@ -25,6 +32,7 @@ pub fn part2(input: &Input) -> u32 {
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::error::located;
 
     const SAMPLE: &str = "1000
 2000
@@ -43,11 +51,16 @@ mod tests {
 
     #[test]
     fn test_part1() {
-        assert_eq!(part1(&generator(SAMPLE)), 24_000);
+        assert_eq!(part1(&generator(SAMPLE).unwrap()), 24_000);
     }
 
     #[test]
     fn test_part2() {
-        assert_eq!(part2(&generator(SAMPLE)), 45_000);
+        assert_eq!(part2(&generator(SAMPLE).unwrap()), 45_000);
+    }
+
+    #[test]
+    fn test_generator() {
+        assert_eq!(located(generator("1000\n2x00")), "2:1 expected calories");
     }
 }
@@ -1,11 +1,25 @@
 // use std::collections::HashMap;
+use crate::error::ParseError;
+
+const DAY: u8 = 2;
 
 type Input = Vec<Vec<char>>;
 
-pub fn generator(input: &str) -> Input {
+pub fn generator(input: &str) -> Result<Input, ParseError> {
     input
         .lines()
-        .map(|line| line.chars().filter(|char| char != &' ').collect())
+        .map(|line| {
+            let (opponent, you) = line
+                .split_once(' ')
+                .ok_or_else(|| ParseError::new(DAY, input, line, "expected two columns"))?;
+            if !matches!(opponent, "A" | "B" | "C") {
+                return Err(ParseError::new(DAY, input, opponent, "expected A, B or C"));
+            }
+            if !matches!(you, "X" | "Y" | "Z") {
+                return Err(ParseError::new(DAY, input, you, "expected X, Y or Z"));
+            }
+            Ok(line.chars().filter(|char| char != &' ').collect())
+        })
         .collect()
 }
 
@@ -94,6 +108,7 @@ fn shape(opponent: &char, outcome: &char) -> char {
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::error::located;
 
     const SAMPLE: &str = "A Y
 B X
@@ -101,11 +116,18 @@ C Z";
 
     #[test]
     fn test_part1() {
-        assert_eq!(part1(&generator(SAMPLE)), 15);
+        assert_eq!(part1(&generator(SAMPLE).unwrap()), 15);
     }
 
     #[test]
     fn test_part2() {
-        assert_eq!(part2(&generator(SAMPLE)), 12);
+        assert_eq!(part2(&generator(SAMPLE).unwrap()), 12);
+    }
+
+    #[test]
+    fn test_generator() {
+        assert_eq!(located(generator("A Y\nD X")), "2:1 expected A, B or C");
+        assert_eq!(located(generator("A Y\nB")), "2:1 expected two columns");
+        assert_eq!(located(generator("C Q")), "1:3 expected X, Y or Z");
     }
 }
@@ -1,10 +1,37 @@
 use std::collections::HashSet;
 
-// figure out lifetimes
+use crate::error::ParseError;
+
+const DAY: u8 = 3;
+
 type Input<'a> = Vec<&'a str>;
 
-pub fn generator(input: &str) -> Input {
-    input.lines().collect()
+pub fn generator(input: &str) -> Result<Input<'_>, ParseError> {
+    input
+        .lines()
+        .map(|line| {
+            if let Some((index, char)) = line
+                .char_indices()
+                .find(|(_, char)| !char.is_ascii_alphabetic())
+            {
+                return Err(ParseError::new(
+                    DAY,
+                    input,
+                    &line[index..index + char.len_utf8()],
+                    "expected an item letter",
+                ));
+            }
+            if line.len() % 2 != 0 {
+                return Err(ParseError::new(
+                    DAY,
+                    input,
+                    line,
+                    "rucksack compartments must be the same size",
+                ));
+            }
+            Ok(line)
+        })
+        .collect()
 }
 
 pub fn part1(input: &Input) -> u32 {
@@ -42,6 +69,7 @@ fn priority(item: &char) -> u8 {
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::error::located;
 
     const SAMPLE: &str = "vJrwpWtwJgWrhcsFMMfFFhFp
 jqHRNqRjqzjGDLGLrsFMfFZSrLrFZsSL
@@ -52,11 +80,23 @@ CrZsJsPPZsGzwwsLwLmpwMDw";
 
     #[test]
     fn test_part1() {
-        assert_eq!(part1(&generator(SAMPLE)), 157);
+        assert_eq!(part1(&generator(SAMPLE).unwrap()), 157);
     }
 
     #[test]
     fn test_part2() {
-        assert_eq!(part2(&generator(SAMPLE)), 70);
+        assert_eq!(part2(&generator(SAMPLE).unwrap()), 70);
+    }
+
+    #[test]
+    fn test_generator() {
+        assert_eq!(
+            located(generator("vJrwpWtwJgWrhcsFMMfFFhFp\nab1d")),
+            "2:3 expected an item letter"
+        );
+        assert_eq!(
+            located(generator("abc")),
+            "1:1 rucksack compartments must be the same size"
+        );
     }
 }
@@ -1,21 +1,40 @@
 use std::collections::HashSet;
 
+use crate::error::ParseError;
+
+const DAY: u8 = 4;
+
 type Input = Vec<Vec<HashSet<u32>>>;
 
-pub fn generator(input: &str) -> Input {
+pub fn generator(input: &str) -> Result<Input, ParseError> {
+    let section = |text: &str| {
+        text.parse::<u32>()
+            .map_err(|_| ParseError::new(DAY, input, text, "expected a section id"))
+    };
+
     input
         .lines()
         .map(|line| {
-            line.split(',')
+            let pairs = line
+                .split(',')
                 .map(|pair| {
-                    let mut sections = pair.split('-');
-                    (sections.next().unwrap().parse::<u32>().unwrap()
-                        ..sections.next().unwrap().parse::<u32>().unwrap() + 1)
-                        .collect::<HashSet<_>>()
+                    let (start, end) = pair
+                        .split_once('-')
+                        .ok_or_else(|| ParseError::new(DAY, input, pair, "expected a range"))?;
+                    Ok((section(start)?..section(end)? + 1).collect::<HashSet<_>>())
                 })
-                .collect::<Vec<_>>()
+                .collect::<Result<Vec<_>, _>>()?;
+            if pairs.len() != 2 {
+                return Err(ParseError::new(
+                    DAY,
+                    input,
+                    line,
+                    "expected a pair of ranges",
+                ));
+            }
+            Ok(pairs)
         })
-        .collect::<Vec<_>>()
+        .collect()
 }
 
 pub fn part1(input: &Input) -> usize {
@@ -40,6 +59,7 @@ pub fn part2(input: &Input) -> usize {
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::error::located;
 
     const SAMPLE: &str = "2-4,6-8
 2-3,4-5
@@ -50,11 +70,24 @@ mod tests {
 
     #[test]
     fn test_part1() {
-        assert_eq!(part1(&generator(SAMPLE)), 2);
+        assert_eq!(part1(&generator(SAMPLE).unwrap()), 2);
     }
 
     #[test]
     fn test_part2() {
-        assert_eq!(part2(&generator(SAMPLE)), 4);
+        assert_eq!(part2(&generator(SAMPLE).unwrap()), 4);
+    }
+
+    #[test]
+    fn test_generator() {
+        assert_eq!(
+            located(generator("2-4,6-8\n2-x,4-5")),
+            "2:3 expected a section id"
+        );
+        assert_eq!(
+            located(generator("2-4,6-8\n2-4")),
+            "2:1 expected a pair of ranges"
+        );
+        assert_eq!(located(generator("24,6-8")), "1:1 expected a range");
     }
 }
@@ -1,6 +1,10 @@
 use itertools::Itertools;
 use regex::Regex;
 
+use crate::error::ParseError;
+
+const DAY: u8 = 5;
+
 type Stack = Vec<std::string::String>;
 #[derive(Debug)]
 pub struct Step {
@@ -11,11 +15,13 @@ pub struct Step {
 
 type Input = (Vec<Stack>, Vec<Step>);
 
-pub fn generator(input: &str) -> Input {
+pub fn generator(input: &str) -> Result<Input, ParseError> {
     let re = Regex::new(r"\[|\]").unwrap();
-    let (crates, operations) = input.split_once("\n\n").unwrap();
+    let (drawing, operations) = input
+        .split_once("\n\n")
+        .ok_or_else(|| ParseError::eof(DAY, input, "expected a blank line before the steps"))?;
 
-    let crates = crates
+    let crates = drawing
         .lines()
         .rev()
         .skip(1)
@@ -30,8 +36,14 @@ pub fn generator(input: &str) -> Input {
         })
         .collect_vec();
 
+    let width = crates
+        .iter()
+        .map(|row| row.len())
+        .max()
+        .ok_or_else(|| ParseError::new(DAY, input, drawing, "expected crates"))?;
+
     // make stacks of crates with an empty stack 0 to align with the inputs
-    let mut stacks: Vec<Vec<String>> = vec![vec![]; crates[0].len() + 1];
+    let mut stacks: Vec<Vec<String>> = vec![vec![]; width + 1];
     for each in crates {
         for (index, _crate) in each.iter().enumerate() {
             if !_crate.is_empty() {
@@ -44,15 +56,36 @@ pub fn generator(input: &str) -> Input {
         .lines()
         .map(|op| {
             let split = op.split_whitespace().collect_vec();
-            Step {
-                crates: split[1].parse().unwrap(),
-                from: split[3].parse().unwrap(),
-                to: split[5].parse().unwrap(),
+            if split.len() != 6 || split[0] != "move" || split[2] != "from" || split[4] != "to" {
+                return Err(ParseError::new(
+                    DAY,
+                    input,
+                    op,
+                    "expected `move N from A to B`",
+                ));
             }
+            let number = |text: &str| {
+                text.parse::<usize>()
+                    .map_err(|_| ParseError::new(DAY, input, text, "expected a number"))
+            };
+            let stack = |text: &str| {
+                number(text).and_then(|index| {
+                    if (1..stacks.len()).contains(&index) {
+                        Ok(index)
+                    } else {
+                        Err(ParseError::new(DAY, input, text, "no such stack"))
+                    }
+                })
+            };
+            Ok(Step {
+                crates: number(split[1])?,
+                from: stack(split[3])?,
+                to: stack(split[5])?,
+            })
         })
-        .collect_vec();
+        .collect::<Result<Vec<_>, _>>()?;
 
-    (stacks, steps)
+    Ok((stacks, steps))
 }
 
 pub fn part1(input: &Input) -> String {
@@ -96,16 +129,14 @@ fn move_crates_9000(stacks: &mut [Stack], step: &Step) {
 
 fn move_crates_9001(stacks: &mut [Stack], step: &Step) {
     let len = stacks[step.from].len();
-    let mut crates = stacks[step.from]
-        .drain((len - step.crates)..)
-        .map(String::from)
-        .collect_vec();
+    let mut crates = stacks[step.from].drain((len - step.crates)..).collect_vec();
     stacks[step.to].append(&mut crates);
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::error::located;
 
     const SAMPLE: &str = "    [D]    
 [N] [C]    
@@ -119,11 +150,31 @@ move 1 from 1 to 2";
 
     #[test]
     fn test_part1() {
-        assert_eq!(part1(&generator(SAMPLE)), "CMZ");
+        assert_eq!(part1(&generator(SAMPLE).unwrap()), "CMZ");
     }
 
     #[test]
     fn test_part2() {
-        assert_eq!(part2(&generator(SAMPLE)), "MCD");
+        assert_eq!(part2(&generator(SAMPLE).unwrap()), "MCD");
+    }
+
+    #[test]
+    fn test_generator() {
+        assert_eq!(
+            located(generator("[A]\n 1 \n\nmove 1 from 1 to 2")),
+            "4:18 no such stack"
+        );
+        assert_eq!(
+            located(generator("[A]\n 1 \n\nmove 1 to 1")),
+            "4:1 expected `move N from A to B`"
+        );
+        assert_eq!(
+            located(generator("[A]\n 1 \n\nmove one from 1 to 1")),
+            "4:6 expected a number"
+        );
+        assert_eq!(
+            located(generator("[A]\n 1 ")),
+            "2:4 expected a blank line before the steps"
+        );
     }
 }
@@ -1,9 +1,11 @@
 use itertools::Itertools;
 
+use crate::error::ParseError;
+
 type Input = Vec<char>;
 
-pub fn generator(input: &str) -> Input {
-    input.chars().collect_vec()
+pub fn generator(input: &str) -> Result<Input, ParseError> {
+    Ok(input.chars().collect_vec())
 }
 
 pub fn part1(input: &Input) -> usize {
@@ -35,19 +37,19 @@ mod tests {
 
     #[test]
     fn test_part1() {
-        assert_eq!(part1(&generator(SAMPLE1)), 7);
-        assert_eq!(part1(&generator(SAMPLE2)), 5);
-        assert_eq!(part1(&generator(SAMPLE3)), 6);
-        assert_eq!(part1(&generator(SAMPLE4)), 10);
-        assert_eq!(part1(&generator(SAMPLE5)), 11);
+        assert_eq!(part1(&generator(SAMPLE1).unwrap()), 7);
+        assert_eq!(part1(&generator(SAMPLE2).unwrap()), 5);
+        assert_eq!(part1(&generator(SAMPLE3).unwrap()), 6);
+        assert_eq!(part1(&generator(SAMPLE4).unwrap()), 10);
+        assert_eq!(part1(&generator(SAMPLE5).unwrap()), 11);
     }
 
     #[test]
     fn test_part2() {
-        assert_eq!(part2(&generator(SAMPLE1)), 19);
-        assert_eq!(part2(&generator(SAMPLE2)), 23);
-        assert_eq!(part2(&generator(SAMPLE3)), 23);
-        assert_eq!(part2(&generator(SAMPLE4)), 29);
-        assert_eq!(part2(&generator(SAMPLE5)), 26);
+        assert_eq!(part2(&generator(SAMPLE1).unwrap()), 19);
+        assert_eq!(part2(&generator(SAMPLE2).unwrap()), 23);
+        assert_eq!(part2(&generator(SAMPLE3).unwrap()), 23);
+        assert_eq!(part2(&generator(SAMPLE4).unwrap()), 29);
+        assert_eq!(part2(&generator(SAMPLE5).unwrap()), 26);
     }
 }
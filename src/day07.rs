@@ -1,84 +1,502 @@
-use std::collections::HashMap;
+use std::{collections::HashSet, fmt, ops::RangeBounds};
+
+use itertools::Itertools;
+
+use crate::error::ParseError;
+
+const DAY: u8 = 7;
 
 enum Operation<'a> {
+    List,
+    MoveRoot,
     MoveUp,
-    MoveDown(String),
+    MoveDown(&'a str),
     Dir(&'a str),
+    File(u32, &'a str),
+}
+
+type Input = FileSystem;
+
+/** index into the FileSystem arena, the root is always 0 */
+pub type NodeId = usize;
+
+#[derive(Debug)]
+pub enum Kind {
+    Dir(Vec<NodeId>),
     File(u32),
 }
 
-type Input = HashMap<String, u32>;
+#[derive(Debug)]
+pub struct Node {
+    pub name: String,
+    pub parent: Option<NodeId>,
+    pub kind: Kind,
+}
+
+/** nodes only ever get appended, so children always come after their parents */
+#[derive(Debug)]
+pub struct FileSystem {
+    nodes: Vec<Node>,
+}
+impl FileSystem {
+    const ROOT: NodeId = 0;
+
+    fn new() -> Self {
+        Self {
+            nodes: vec![Node {
+                name: String::from("/"),
+                parent: None,
+                kind: Kind::Dir(Vec::new()),
+            }],
+        }
+    }
+
+    #[allow(dead_code)]
+    pub fn node(&self, id: NodeId) -> &Node {
+        &self.nodes[id]
+    }
+
+    pub fn child(&self, dir: NodeId, name: &str) -> Option<NodeId> {
+        match &self.nodes[dir].kind {
+            Kind::Dir(children) => children
+                .iter()
+                .copied()
+                .find(|child| self.nodes[*child].name == name),
+            Kind::File(_) => None,
+        }
+    }
+
+    /** returns the existing node when `name` is already in `dir` */
+    fn add(&mut self, dir: NodeId, name: &str, kind: Kind) -> NodeId {
+        if let Some(existing) = self.child(dir, name) {
+            return existing;
+        }
+        let id = self.nodes.len();
+        self.nodes.push(Node {
+            name: name.to_string(),
+            parent: Some(dir),
+            kind,
+        });
+        if let Kind::Dir(children) = &mut self.nodes[dir].kind {
+            children.push(id);
+        }
+        id
+    }
+
+    #[allow(dead_code)]
+    pub fn path(&self, id: NodeId) -> String {
+        let mut names = Vec::new();
+        let mut current = id;
+        while let Some(parent) = self.nodes[current].parent {
+            names.push(self.nodes[current].name.as_str());
+            current = parent;
+        }
+        format!("/{}", names.iter().rev().join("/"))
+    }
+
+    /** either one contains the other */
+    pub fn nested(&self, a: NodeId, b: NodeId) -> bool {
+        self.ancestors(a).any(|id| id == b) || self.ancestors(b).any(|id| id == a)
+    }
+
+    /** itself, then each parent up to the root */
+    fn ancestors(&self, id: NodeId) -> impl Iterator<Item = NodeId> + '_ {
+        std::iter::successors(Some(id), |id| self.nodes[*id].parent)
+    }
+
+    pub fn is_dir(&self, id: NodeId) -> bool {
+        matches!(self.nodes[id].kind, Kind::Dir(_))
+    }
+
+    /** total size of every node, indexed by NodeId */
+    pub fn sizes(&self) -> Vec<u32> {
+        let mut sizes: Vec<u32> = self
+            .nodes
+            .iter()
+            .map(|node| match node.kind {
+                Kind::File(size) => size,
+                Kind::Dir(_) => 0,
+            })
+            .collect();
+        // walking backwards rolls each child up before its parent is added to its own parent
+        for id in (1..self.nodes.len()).rev() {
+            if let Some(parent) = self.nodes[id].parent {
+                sizes[parent] += sizes[id];
+            }
+        }
+        sizes
+    }
+
+    #[allow(dead_code)]
+    pub fn du(&self, id: NodeId) -> u32 {
+        self.sizes()[id]
+    }
+
+    pub fn dirs(&self) -> impl Iterator<Item = NodeId> + '_ {
+        (0..self.nodes.len()).filter(|id| self.is_dir(*id))
+    }
+
+    pub fn files(&self) -> impl Iterator<Item = NodeId> + '_ {
+        (0..self.nodes.len()).filter(|id| !self.is_dir(*id))
+    }
+
+    /** directories whose total size falls in `range`, like `find -type d -size` */
+    pub fn find_dirs(&self, range: impl RangeBounds<u32>) -> Vec<NodeId> {
+        let sizes = self.sizes();
+        self.dirs()
+            .filter(|id| range.contains(&sizes[*id]))
+            .collect()
+    }
+
+    #[allow(dead_code)]
+    pub fn find_files(&self, range: impl RangeBounds<u32>) -> Vec<NodeId> {
+        let sizes = self.sizes();
+        self.files()
+            .filter(|id| range.contains(&sizes[*id]))
+            .collect()
+    }
+
+    #[allow(dead_code)]
+    pub fn largest_files(&self, count: usize) -> Vec<NodeId> {
+        let sizes = self.sizes();
+        self.files()
+            .sorted_by_key(|id| std::cmp::Reverse(sizes[*id]))
+            .take(count)
+            .collect()
+    }
+
+    /** same layout as the puzzle text */
+    #[allow(dead_code)]
+    pub fn tree(&self) -> String {
+        let mut lines: Vec<String> = Vec::new();
+        self.tree_lines(Self::ROOT, 0, &mut lines);
+        lines.join("\n")
+    }
+
+    fn tree_lines(&self, id: NodeId, depth: usize, lines: &mut Vec<String>) {
+        let node = &self.nodes[id];
+        let indent = "  ".repeat(depth);
+        match &node.kind {
+            Kind::Dir(children) => {
+                lines.push(format!("{}- {} (dir)", indent, node.name));
+                for child in children {
+                    self.tree_lines(*child, depth + 1, lines);
+                }
+            }
+            Kind::File(size) => {
+                lines.push(format!("{}- {} (file, size={})", indent, node.name, size))
+            }
+        }
+    }
+}
+
+/** something odd in the terminal session that was replayed anyway, `line` is 1-based */
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Warning {
+    /** listed again, entries it already has aren't counted twice */
+    Relisted { line: usize, path: String },
+    /** no listing had shown this directory before moving into it */
+    UnknownDir { line: usize, path: String },
+    /** already at `/`, so this stays there */
+    PastRoot { line: usize },
+    /** a `cd` or `dir` naming something already seen as a file, the file stays */
+    NotADir { line: usize, path: String },
+    /** a file named like a directory already seen, the directory stays */
+    NotAFile { line: usize, path: String },
+    /** the first size seen is the one kept */
+    DuplicateFile {
+        line: usize,
+        path: String,
+        size: u32,
+        kept: u32,
+    },
+}
+impl fmt::Display for Warning {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Warning::Relisted { line, path } => {
+                write!(f, "line {}: {} was already listed", line, path)
+            }
+            Warning::UnknownDir { line, path } => {
+                write!(f, "line {}: cd into {} before it was listed", line, path)
+            }
+            Warning::PastRoot { line } => write!(f, "line {}: cd .. past /", line),
+            Warning::NotADir { line, path } => {
+                write!(f, "line {}: {} is a file, not a directory", line, path)
+            }
+            Warning::NotAFile { line, path } => {
+                write!(f, "line {}: {} is a directory, not a file", line, path)
+            }
+            Warning::DuplicateFile {
+                line,
+                path,
+                size,
+                kept,
+            } => write!(
+                f,
+                "line {}: {} listed again with size {}, keeping {}",
+                line, path, size, kept
+            ),
+        }
+    }
+}
+
+pub struct Replay {
+    pub fs: FileSystem,
+    #[allow(dead_code)]
+    pub warnings: Vec<Warning>,
+}
 
-pub fn generator(input: &str) -> Input {
-    let mut tree: Input = HashMap::new();
-    tree.insert(String::from("/"), 0);
-    let mut pointer: Vec<String> = Vec::new();
+pub fn generator(input: &str) -> Result<Input, ParseError> {
+    replay(input).map(|replay| replay.fs)
+}
 
-    for line in input.lines() {
-        if let Some(op) = parse(line) {
-            match op {
-                Operation::MoveUp => {
-                    pointer.pop();
+/** rebuild the tree from the session, noting anything that doesn't add up */
+pub fn replay(input: &str) -> Result<Replay, ParseError> {
+    let mut fs = FileSystem::new();
+    let mut warnings: Vec<Warning> = Vec::new();
+    let mut listed: HashSet<NodeId> = HashSet::new();
+    let mut relisting = false;
+    let mut cwd = FileSystem::ROOT;
+
+    for (index, line) in input.lines().enumerate() {
+        let line_number = index + 1;
+        match parse(input, line)? {
+            Operation::List => {
+                relisting = !listed.insert(cwd);
+                if relisting {
+                    warnings.push(Warning::Relisted {
+                        line: line_number,
+                        path: fs.path(cwd),
+                    });
+                }
+            }
+            Operation::MoveRoot => cwd = FileSystem::ROOT,
+            Operation::MoveUp => match fs.nodes[cwd].parent {
+                Some(parent) => cwd = parent,
+                None => warnings.push(Warning::PastRoot { line: line_number }),
+            },
+            Operation::MoveDown(dir) => {
+                let known = fs.child(cwd, dir);
+                if known.is_none() {
+                    warnings.push(Warning::UnknownDir {
+                        line: line_number,
+                        path: fs.path(cwd).trim_end_matches('/').to_string() + "/" + dir,
+                    });
                 }
-                Operation::MoveDown(dir) => {
-                    pointer.push(dir.to_string());
+                match known {
+                    Some(id) if !fs.is_dir(id) => warnings.push(Warning::NotADir {
+                        line: line_number,
+                        path: fs.path(id),
+                    }),
+                    _ => cwd = fs.add(cwd, dir, Kind::Dir(Vec::new())),
                 }
-                Operation::Dir(dir) => {
-                    tree.entry(pointer.concat() + dir).or_insert(0);
+            }
+            Operation::Dir(dir) => {
+                let id = fs.add(cwd, dir, Kind::Dir(Vec::new()));
+                if !fs.is_dir(id) {
+                    warnings.push(Warning::NotADir {
+                        line: line_number,
+                        path: fs.path(id),
+                    });
                 }
-                Operation::File(file) => {
-                    // roll up sizes to parent directories
-                    for index in 0..=pointer.len() {
-                        tree.entry(pointer[0..index].concat())
-                            .and_modify(|dir| *dir += file);
+            }
+            Operation::File(size, name) => {
+                let count = fs.nodes.len();
+                let id = fs.add(cwd, name, Kind::File(size));
+                if let Kind::File(kept) = fs.nodes[id].kind {
+                    // a relisting repeats every file, only a changed size is worth a mention
+                    let added = id >= count;
+                    if !added && (!relisting || kept != size) {
+                        warnings.push(Warning::DuplicateFile {
+                            line: line_number,
+                            path: fs.path(id),
+                            size,
+                            kept,
+                        });
                     }
+                } else {
+                    warnings.push(Warning::NotAFile {
+                        line: line_number,
+                        path: fs.path(id),
+                    });
                 }
             }
         }
     }
 
-    tree
+    Ok(Replay { fs, warnings })
 }
 
 pub fn part1(input: &Input) -> u32 {
-    input
-        .iter()
-        .filter_map(|(_path, dir)| if dir < &100_000 { Some(dir) } else { None })
-        .sum()
+    let sizes = input.sizes();
+    input.find_dirs(..100_000).iter().map(|id| sizes[*id]).sum()
 }
 
 pub fn part2(input: &Input) -> u32 {
-    const TOTAL: u32 = 70_000_000;
-    const FREE: u32 = 30_000_000;
-    let unused: u32 = TOTAL - input.get("/").unwrap();
-    let goal: u32 = FREE - unused;
+    DiskPolicy::default()
+        .smallest_dir(input)
+        .unwrap_or_else(|| panic!("no directory frees enough space"))
+        .freed
+}
+
+/** how big the disk is, how much has to be free, and how many directories a deletion may span */
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct DiskPolicy {
+    pub total: u32,
+    pub required: u32,
+    pub max_dirs: usize,
+}
+impl Default for DiskPolicy {
+    fn default() -> Self {
+        Self {
+            total: 70_000_000,
+            required: 30_000_000,
+            max_dirs: 3,
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Deletion {
+    pub dirs: Vec<NodeId>,
+    pub freed: u32,
+}
+
+impl DiskPolicy {
+    /** bytes that still have to go, 0 when there's already enough room */
+    pub fn shortfall(&self, fs: &FileSystem) -> u32 {
+        let used = fs.sizes()[FileSystem::ROOT];
+        self.required
+            .saturating_sub(self.total.saturating_sub(used))
+    }
+
+    /** the smallest single directory that frees enough, None when even `/` won't do */
+    pub fn smallest_dir(&self, fs: &FileSystem) -> Option<Deletion> {
+        let sizes = fs.sizes();
+        let goal = self.shortfall(fs);
+        if goal == 0 {
+            return Some(Deletion {
+                dirs: Vec::new(),
+                freed: 0,
+            });
+        }
+
+        fs.find_dirs(goal..)
+            .into_iter()
+            .min_by_key(|id| sizes[*id])
+            .map(|id| Deletion {
+                dirs: vec![id],
+                freed: sizes[id],
+            })
+    }
+
+    /** fewest bytes freed using up to `max_dirs` unrelated directories, nested ones would count twice */
+    #[allow(dead_code)]
+    pub fn smallest_set(&self, fs: &FileSystem) -> Option<Deletion> {
+        let sizes = fs.sizes();
+        let goal = self.shortfall(fs);
+        let mut best = self.smallest_dir(fs);
+        if goal == 0 {
+            return best;
+        }
+
+        // anything big enough alone is already covered by smallest_dir
+        let candidates = fs
+            .find_dirs(..goal)
+            .into_iter()
+            .sorted_by_key(|id| std::cmp::Reverse(sizes[*id]))
+            .collect_vec();
+        let mut chosen: Vec<NodeId> = Vec::new();
+        self.search(fs, &sizes, &candidates, goal, &mut chosen, 0, &mut best);
 
-    *input
-        .iter()
-        .filter_map(|(_path, dir)| if dir >= &goal { Some(dir) } else { None })
-        .min()
-        .unwrap()
+        best
+    }
+
+    #[allow(clippy::too_many_arguments)]
+    fn search(
+        &self,
+        fs: &FileSystem,
+        sizes: &[u32],
+        candidates: &[NodeId],
+        goal: u32,
+        chosen: &mut Vec<NodeId>,
+        freed: u32,
+        best: &mut Option<Deletion>,
+    ) {
+        if freed >= goal {
+            if best.as_ref().is_none_or(|best| freed < best.freed) {
+                *best = Some(Deletion {
+                    dirs: chosen.clone(),
+                    freed,
+                });
+            }
+            return;
+        }
+        if chosen.len() == self.max_dirs {
+            return;
+        }
+
+        for (index, id) in candidates.iter().enumerate() {
+            let total = freed + sizes[*id];
+            // sorted largest first, so nothing later can reach the goal once these can't
+            let reachable: u32 = candidates[index..]
+                .iter()
+                .take(self.max_dirs - chosen.len())
+                .map(|id| sizes[*id])
+                .sum();
+            if freed + reachable < goal {
+                return;
+            }
+            if best.as_ref().is_some_and(|best| total >= best.freed) {
+                continue;
+            }
+            if chosen.iter().any(|other| fs.nested(*id, *other)) {
+                continue;
+            }
+
+            chosen.push(*id);
+            self.search(
+                fs,
+                sizes,
+                &candidates[index + 1..],
+                goal,
+                chosen,
+                total,
+                best,
+            );
+            chosen.pop();
+        }
+    }
 }
 
-fn parse(line: &str) -> Option<Operation> {
-    if line.starts_with("$ ls") {
-        None
+fn parse<'a>(input: &str, line: &'a str) -> Result<Operation<'a>, ParseError> {
+    if line == "$ ls" {
+        Ok(Operation::List)
+    } else if line == "$ cd /" {
+        Ok(Operation::MoveRoot)
     } else if line == "$ cd .." {
-        Some(Operation::MoveUp)
-    } else if line.starts_with("$ cd") {
-        Some(Operation::MoveDown(line[5..].to_string()))
+        Ok(Operation::MoveUp)
+    } else if let Some(dir) = line.strip_prefix("$ cd ") {
+        Ok(Operation::MoveDown(dir))
     } else if let Some(name) = line.strip_prefix("dir ") {
-        Some(Operation::Dir(name))
+        Ok(Operation::Dir(name))
+    } else if line.starts_with('$') {
+        Err(ParseError::new(DAY, input, line, "unknown command"))
     } else {
-        Some(Operation::File(
-            line.split_whitespace().next().unwrap().parse().unwrap(),
-        ))
+        let (size, name) = line
+            .split_once(' ')
+            .ok_or_else(|| ParseError::new(DAY, input, line, "expected `size name`"))?;
+        size.parse()
+            .map(|size| Operation::File(size, name))
+            .map_err(|_| ParseError::new(DAY, input, size, "expected a file size"))
     }
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::error::located;
 
     const SAMPLE: &str = "$ cd /
 $ ls
@@ -106,11 +524,206 @@ $ ls
 
     #[test]
     fn test_part1() {
-        assert_eq!(part1(&generator(SAMPLE)), 95437);
+        assert_eq!(part1(&generator(SAMPLE).unwrap()), 95437);
     }
 
     #[test]
     fn test_part2() {
-        assert_eq!(part2(&generator(SAMPLE)), 24933642);
+        assert_eq!(part2(&generator(SAMPLE).unwrap()), 24933642);
+    }
+
+    #[test]
+    fn test_disk_policy() {
+        let fs = generator(SAMPLE).unwrap();
+        let policy = DiskPolicy::default();
+        assert_eq!(policy.shortfall(&fs), 8381165);
+        let single = policy.smallest_dir(&fs).unwrap();
+        assert_eq!(fs.path(single.dirs[0]), "/d");
+        assert_eq!(policy.smallest_set(&fs), Some(single));
+
+        let fs = generator(
+            "$ cd /
+$ ls
+dir x
+dir y
+dir z
+$ cd x
+$ ls
+60 a
+dir w
+$ cd w
+$ ls
+45 b
+$ cd /
+$ cd y
+$ ls
+50 c
+$ cd /
+$ cd z
+$ ls
+200 d",
+        )
+        .unwrap();
+        let policy = DiskPolicy {
+            total: 400,
+            required: 155,
+            max_dirs: 2,
+        };
+        assert_eq!(policy.shortfall(&fs), 110);
+        assert_eq!(policy.smallest_dir(&fs).unwrap().freed, 200);
+        // x already holds w, so x + w would count b twice
+        let set = policy.smallest_set(&fs).unwrap();
+        assert_eq!(set.freed, 155);
+        assert_eq!(
+            set.dirs.iter().map(|id| fs.path(*id)).collect_vec(),
+            vec!["/x", "/y"]
+        );
+        assert_eq!(
+            DiskPolicy {
+                max_dirs: 1,
+                ..policy
+            }
+            .smallest_set(&fs)
+            .unwrap()
+            .freed,
+            200
+        );
+    }
+
+    #[test]
+    fn test_tree() {
+        let fs = generator(SAMPLE).unwrap();
+        assert_eq!(
+            fs.tree(),
+            "- / (dir)
+  - a (dir)
+    - e (dir)
+      - i (file, size=584)
+    - f (file, size=29116)
+    - g (file, size=2557)
+    - h.lst (file, size=62596)
+  - b.txt (file, size=14848514)
+  - c.dat (file, size=8504156)
+  - d (dir)
+    - j (file, size=4060174)
+    - d.log (file, size=8033020)
+    - d.ext (file, size=5626152)
+    - k (file, size=7214296)"
+        );
+
+        let largest = fs.largest_files(2);
+        assert_eq!(fs.path(largest[0]), "/b.txt");
+        assert_eq!(fs.path(largest[1]), "/c.dat");
+        assert_eq!(fs.find_files(..1_000).len(), 1);
+        assert_eq!(fs.du(fs.child(0, "a").unwrap()), 94853);
+    }
+
+    #[test]
+    fn test_replay() {
+        let replay = replay(
+            "$ cd /
+$ cd ..
+$ ls
+dir a
+10 x
+$ cd a
+$ ls
+20 y
+20 y
+$ cd ..
+$ ls
+dir a
+12 x
+$ cd b
+$ ls
+5 z
+$ cd /
+$ cd x
+$ ls
+dir x
+7 a",
+        )
+        .unwrap();
+
+        assert_eq!(
+            replay.warnings,
+            vec![
+                Warning::PastRoot { line: 2 },
+                Warning::DuplicateFile {
+                    line: 9,
+                    path: String::from("/a/y"),
+                    size: 20,
+                    kept: 20
+                },
+                Warning::Relisted {
+                    line: 11,
+                    path: String::from("/")
+                },
+                Warning::DuplicateFile {
+                    line: 13,
+                    path: String::from("/x"),
+                    size: 12,
+                    kept: 10
+                },
+                Warning::UnknownDir {
+                    line: 14,
+                    path: String::from("/b")
+                },
+                Warning::NotADir {
+                    line: 18,
+                    path: String::from("/x")
+                },
+                Warning::Relisted {
+                    line: 19,
+                    path: String::from("/")
+                },
+                Warning::NotADir {
+                    line: 20,
+                    path: String::from("/x")
+                },
+                Warning::NotAFile {
+                    line: 21,
+                    path: String::from("/a")
+                },
+            ]
+        );
+        assert_eq!(replay.fs.du(0), 35);
+    }
+
+    #[test]
+    fn test_paths() {
+        // these collided when paths were built without separators
+        let fs = generator(
+            "$ cd /
+$ ls
+dir a
+dir ab
+$ cd a
+$ ls
+dir bc
+$ cd bc
+$ ls
+1 x
+$ cd /
+$ cd ab
+$ ls
+dir c
+$ cd c
+$ ls
+2 y",
+        )
+        .unwrap();
+        let paths = fs.dirs().map(|id| fs.path(id)).collect_vec();
+        assert_eq!(paths, vec!["/", "/a", "/ab", "/a/bc", "/ab/c"]);
+        assert_eq!(fs.find_dirs(1..=1).len(), 2);
+    }
+
+    #[test]
+    fn test_generator() {
+        assert_eq!(located(generator("$ cd /\n$ rm x")), "2:1 unknown command");
+        assert_eq!(
+            located(generator("$ cd /\n$ ls\nabc x")),
+            "3:1 expected a file size"
+        );
     }
 }
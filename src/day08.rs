@@ -1,23 +1,46 @@
-use std::{
-    borrow::Borrow,
-    collections::{HashMap, HashSet},
-};
+use std::collections::{HashMap, HashSet};
+
+use crate::error::ParseError;
+
+const DAY: u8 = 8;
 
 type Input = Vec<Vec<u32>>;
 type Seen = HashSet<(usize, usize)>;
 type Scores = HashMap<(usize, usize), u32>;
 
-pub fn generator(input: &str) -> Input {
+pub fn generator(input: &str) -> Result<Input, ParseError> {
     let grid: Vec<Vec<u32>> = input
         .lines()
         .map(|line| {
-            line.chars()
-                .map(|char| char.to_digit(10).unwrap())
+            line.char_indices()
+                .map(|(index, char)| {
+                    char.to_digit(10).ok_or_else(|| {
+                        ParseError::new(
+                            DAY,
+                            input,
+                            &line[index..index + char.len_utf8()],
+                            "expected a tree height",
+                        )
+                    })
+                })
                 .collect()
         })
-        .collect();
+        .collect::<Result<_, _>>()?;
+
+    let width = grid
+        .first()
+        .map(Vec::len)
+        .ok_or_else(|| ParseError::eof(DAY, input, "expected trees"))?;
+    if let Some(line) = input.lines().find(|line| line.len() != width) {
+        return Err(ParseError::new(
+            DAY,
+            input,
+            line,
+            "rows must be the same width",
+        ));
+    }
 
-    grid
+    Ok(grid)
 }
 
 pub fn part1(input: &Input) -> usize {
@@ -124,10 +147,7 @@ fn find_score(
 
 /** borrow cells to be compatible with find_visible signature TODO better way */
 fn make_rows(input: &Input) -> Vec<Vec<&u32>> {
-    input
-        .iter()
-        .map(|row| row.iter().map(|col| col.borrow()).collect())
-        .collect()
+    input.iter().map(|row| row.iter().collect()).collect()
 }
 
 /** rotate grid counter clockwise 90 so columns can also be sliced for searching */
@@ -146,6 +166,7 @@ fn make_cols(input: &Input, row_max: usize, col_max: usize) -> Vec<Vec<&u32>> {
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::error::located;
 
     const SAMPLE: &str = "30373
 25512
@@ -155,11 +176,21 @@ mod tests {
 
     #[test]
     fn test_part1() {
-        assert_eq!(part1(&generator(SAMPLE)), 21);
+        assert_eq!(part1(&generator(SAMPLE).unwrap()), 21);
     }
 
     #[test]
     fn test_part2() {
-        assert_eq!(part2(&generator(SAMPLE)), 8);
+        assert_eq!(part2(&generator(SAMPLE).unwrap()), 8);
+    }
+
+    #[test]
+    fn test_generator() {
+        assert_eq!(located(generator("303\n2x5")), "2:2 expected a tree height");
+        assert_eq!(
+            located(generator("30373\n255")),
+            "2:1 rows must be the same width"
+        );
+        assert_eq!(located(generator("")), "1:1 expected trees");
     }
 }
@@ -1,30 +1,53 @@
 use std::collections::HashSet;
 
-type Input<'a> = Vec<Motion<'a>>;
-/** x, y */
-type Coord = (i32, i32);
+use crate::{
+    error::ParseError,
+    geom::{Direction, Point},
+};
+
+const DAY: u8 = 9;
+
+type Input = Vec<Motion>;
+type Coord = Point<i32>;
 type Rope = Vec<Coord>;
 
-pub struct Motion<'a> {
-    direction: &'a str,
+pub struct Motion {
+    direction: Direction,
     steps: usize,
 }
 
-pub fn generator(input: &str) -> Input {
+pub fn generator(input: &str) -> Result<Input, ParseError> {
     input
         .lines()
         .map(|line| {
-            let (direction, s) = line.split_once(' ').unwrap();
-            Motion {
-                direction,
-                steps: s.parse().unwrap(),
-            }
+            let (direction, s) = line
+                .split_once(' ')
+                .ok_or_else(|| ParseError::new(DAY, input, line, "expected a motion"))?;
+            Ok(Motion {
+                direction: match direction {
+                    "U" => Direction::Up,
+                    "D" => Direction::Down,
+                    "L" => Direction::Left,
+                    "R" => Direction::Right,
+                    _ => {
+                        return Err(ParseError::new(
+                            DAY,
+                            input,
+                            direction,
+                            "expected U, D, L or R",
+                        ))
+                    }
+                },
+                steps: s
+                    .parse()
+                    .map_err(|_| ParseError::new(DAY, input, s, "expected a step count"))?,
+            })
         })
         .collect()
 }
 
 pub fn part1(input: &Input) -> usize {
-    let mut rope: Rope = vec![(0, 0); 2];
+    let mut rope: Rope = vec![Point::new(0, 0); 2];
     let mut visited: HashSet<Coord> = HashSet::new();
 
     for motion in input.iter() {
@@ -35,7 +58,7 @@ pub fn part1(input: &Input) -> usize {
 }
 
 pub fn part2(input: &Input) -> usize {
-    let mut rope: Rope = vec![(0, 0); 10];
+    let mut rope: Rope = vec![Point::new(0, 0); 10];
     let mut visited: HashSet<Coord> = HashSet::new();
 
     for motion in input.iter() {
@@ -48,7 +71,7 @@ pub fn part2(input: &Input) -> usize {
 fn move_rope(motion: &Motion, rope: &mut Rope, visited: &mut HashSet<Coord>) {
     let tail = rope.len() - 1;
     for _ in 0..motion.steps {
-        move_head(motion.direction, &mut rope[0]);
+        rope[0] += motion.direction.offset();
 
         for head in 0..tail {
             move_knot(rope, head, head + 1)
@@ -58,57 +81,18 @@ fn move_rope(motion: &Motion, rope: &mut Rope, visited: &mut HashSet<Coord>) {
     }
 }
 
-fn move_head(direction: &str, head: &mut Coord) {
-    match direction {
-        "U" => {
-            head.1 += 1;
-        }
-        "D" => {
-            head.1 -= 1;
-        }
-        "L" => {
-            head.0 -= 1;
-        }
-        "R" => {
-            head.0 += 1;
-        }
-        _ => unreachable!(),
-    }
-}
-
 fn move_knot(rope: &mut Rope, head: usize, tail: usize) {
-    let distance: Coord = (rope[head].0 - rope[tail].0, rope[head].1 - rope[tail].1);
-    let tail = &mut rope[tail];
-
-    if distance.0 == 0 && distance.1 > 1 {
-        tail.1 += 1; // up
-    }
-    if distance.0 == 0 && distance.1 < -1 {
-        tail.1 -= 1; // down
-    }
-    if distance.0 < -1 && distance.1 == 0 {
-        tail.0 -= 1; // left
-    }
-    if distance.0 > 1 && distance.1 == 0 {
-        tail.0 += 1; // right
-    }
-    if (distance.0 <= -1 && distance.1 > 1) || (distance.0 < -1 && distance.1 >= 1) {
-        *tail = (tail.0 - 1, tail.1 + 1); // up/left
-    }
-    if (distance.0 >= 1 && distance.1 > 1) || (distance.0 > 1 && distance.1 >= 1) {
-        *tail = (tail.0 + 1, tail.1 + 1); // up/right
-    }
-    if (distance.0 <= -1 && distance.1 < -1) || (distance.0 < -1 && distance.1 <= -1) {
-        *tail = (tail.0 - 1, tail.1 - 1); // down/left
-    }
-    if (distance.0 >= 1 && distance.1 < -1) || (distance.0 > 1 && distance.1 <= -1) {
-        *tail = (tail.0 + 1, tail.1 - 1); // down/right
+    // only follow once the knots stop touching, moving one step along each axis that differs
+    if rope[head].chebyshev_distance(&rope[tail]) > 1 {
+        let step = (rope[head] - rope[tail]).signum();
+        rope[tail] += step;
     }
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::error::located;
 
     const SAMPLE1: &str = "R 4
 U 4
@@ -130,12 +114,19 @@ U 20";
 
     #[test]
     fn test_part1() {
-        assert_eq!(part1(&generator(SAMPLE1)), 13);
+        assert_eq!(part1(&generator(SAMPLE1).unwrap()), 13);
     }
 
     #[test]
     fn test_part2() {
-        assert_eq!(part2(&generator(SAMPLE1)), 1);
-        assert_eq!(part2(&generator(SAMPLE2)), 36);
+        assert_eq!(part2(&generator(SAMPLE1).unwrap()), 1);
+        assert_eq!(part2(&generator(SAMPLE2).unwrap()), 36);
+    }
+
+    #[test]
+    fn test_generator() {
+        assert_eq!(located(generator("R 4\nQ 2")), "2:1 expected U, D, L or R");
+        assert_eq!(located(generator("R x")), "1:3 expected a step count");
+        assert_eq!(located(generator("R4")), "1:1 expected a motion");
     }
 }
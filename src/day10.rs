@@ -1,126 +1,490 @@
+use std::{fmt, io, ops::RangeBounds};
+
 use itertools::Itertools;
+use serde_json::{json, Value};
+
+use crate::{
+    error::ParseError,
+    ocr::{self, OcrError},
+};
+
+const DAY: u8 = 10;
+
+type Input = Program;
+pub type Registers = Vec<i32>;
+
+/** one entry in the instruction set, applied once its last cycle is over */
+#[derive(Clone, Copy)]
+pub struct Op {
+    pub name: &'static str,
+    pub cycles: u32,
+    pub operands: usize,
+    pub execute: fn(&mut Registers, &[i32]),
+}
+
+#[derive(Clone)]
+pub struct InstructionSet {
+    /** names and starting values, X is always first */
+    pub registers: Vec<(&'static str, i32)>,
+    ops: Vec<Op>,
+}
+impl InstructionSet {
+    pub fn standard() -> Self {
+        Self {
+            registers: vec![("x", 1)],
+            ops: vec![
+                Op {
+                    name: "noop",
+                    cycles: 1,
+                    operands: 0,
+                    execute: |_, _| {},
+                },
+                Op {
+                    name: "addx",
+                    cycles: 2,
+                    operands: 1,
+                    execute: |registers, args| registers[0] += args[0],
+                },
+            ],
+        }
+    }
+
+    /** adds an op, or replaces the one with the same name */
+    #[allow(dead_code)]
+    pub fn with(mut self, op: Op) -> Self {
+        match self.ops.iter().position(|other| other.name == op.name) {
+            Some(index) => self.ops[index] = op,
+            None => self.ops.push(op),
+        }
+        self
+    }
+
+    #[allow(dead_code)]
+    pub fn with_register(mut self, name: &'static str, value: i32) -> Self {
+        self.registers.push((name, value));
+        self
+    }
 
-type Input = Vec<Instruction>;
-type Crt = Vec<Vec<String>>;
+    pub fn op(&self, instruction: &Instruction) -> &Op {
+        &self.ops[instruction.op]
+    }
+
+    pub fn assemble(&self, input: &str) -> Result<Program, ParseError> {
+        let instructions = input
+            .lines()
+            .map(|line| {
+                let mut words = line.split_whitespace();
+                let name = words.next().unwrap_or(line);
+                let op = self
+                    .ops
+                    .iter()
+                    .position(|op| op.name == name)
+                    .ok_or_else(|| ParseError::new(DAY, input, name, "unknown instruction"))?;
+                let args = words
+                    .map(|arg| {
+                        arg.parse()
+                            .map_err(|_| ParseError::new(DAY, input, arg, "expected a number"))
+                    })
+                    .collect::<Result<Vec<i32>, _>>()?;
+                if args.len() != self.ops[op].operands {
+                    return Err(ParseError::new(
+                        DAY,
+                        input,
+                        line,
+                        format!("{} takes {} operand(s)", name, self.ops[op].operands),
+                    ));
+                }
+                Ok(Instruction { op, args })
+            })
+            .collect::<Result<_, _>>()?;
+
+        Ok(Program {
+            set: self.clone(),
+            instructions,
+        })
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Instruction {
+    /** index into the InstructionSet it was assembled with */
+    pub op: usize,
+    pub args: Vec<i32>,
+}
 
 #[derive(Clone)]
-pub enum Instruction {
-    Noop,
-    Addx(i32),
+pub struct Program {
+    pub set: InstructionSet,
+    pub instructions: Vec<Instruction>,
+}
+
+/** disassembles back to the puzzle's text */
+impl fmt::Display for Program {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let lines = (0..self.instructions.len()).map(|pc| self.line(pc));
+        write!(f, "{}", lines.format("\n"))
+    }
+}
+impl Program {
+    /** a single instruction as text */
+    pub fn line(&self, pc: usize) -> String {
+        let instruction = &self.instructions[pc];
+        std::iter::once(self.set.op(instruction).name.to_string())
+            .chain(instruction.args.iter().map(|arg| arg.to_string()))
+            .join(" ")
+    }
+}
+
+/** the machine as seen during a cycle, before anything finishing on it is applied */
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Tick {
+    pub cycle: u32,
+    pub pc: usize,
+    pub registers: Registers,
 }
 
-struct Processor {
-    cycle: i32,
-    register: i32,
-    blocking: u8,
-    instruction: Option<Instruction>,
+pub struct Vm<'a> {
+    program: &'a Program,
+    pub registers: Registers,
+    /** the next cycle to run, starting at 1 */
+    pub cycle: u32,
+    pc: usize,
+    elapsed: u32,
 }
-impl Processor {
-    fn new() -> Processor {
-        Processor {
+impl<'a> Vm<'a> {
+    pub fn new(program: &'a Program) -> Self {
+        Self {
+            program,
+            registers: program.set.registers.iter().map(|r| r.1).collect(),
             cycle: 1,
-            register: 1,
-            blocking: 0,
-            instruction: None,
+            pc: 0,
+            elapsed: 0,
         }
     }
 
-    fn start_processing(&mut self, instruction: Option<&Instruction>) {
-        match instruction {
-            Some(x) => {
-                self.instruction = Some(x.clone());
-                match x {
-                    Instruction::Noop => self.blocking = 1,
-                    Instruction::Addx(_) => self.blocking = 2,
-                }
-            }
-            None => self.blocking = 0,
+    /** runs a single cycle, None once the program is done */
+    pub fn step(&mut self) -> Option<Tick> {
+        let instruction = self.program.instructions.get(self.pc)?;
+        let op = self.program.set.op(instruction);
+        let tick = Tick {
+            cycle: self.cycle,
+            pc: self.pc,
+            registers: self.registers.clone(),
+        };
+
+        self.cycle += 1;
+        self.elapsed += 1;
+        if self.elapsed >= op.cycles {
+            (op.execute)(&mut self.registers, &instruction.args);
+            self.elapsed = 0;
+            self.pc += 1;
         }
+
+        Some(tick)
+    }
+
+    pub fn finished(&self) -> bool {
+        self.pc >= self.program.instructions.len()
     }
 
-    fn finish_processing(&mut self) {
-        if let Some(Instruction::Addx(x)) = self.instruction {
-            self.register += x
+    /** calls `observe` on every cycle until the program ends */
+    pub fn run(&mut self, mut observe: impl FnMut(&Tick)) {
+        while let Some(tick) = self.step() {
+            observe(&tick);
         }
-        self.instruction = None;
     }
+}
 
-    fn tick(&mut self) {
-        self.cycle += 1;
-        self.blocking -= 1;
+pub fn generator(input: &str) -> Result<Input, ParseError> {
+    InstructionSet::standard().assemble(input)
+}
+
+/** screen size, sprite size and which cycles count towards the signal strength */
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CrtConfig {
+    pub width: usize,
+    pub height: usize,
+    pub sprite_width: usize,
+    /** the first sampled cycle, then every `sample_every` after it */
+    pub first_sample: u32,
+    pub sample_every: u32,
+}
+impl Default for CrtConfig {
+    fn default() -> Self {
+        Self {
+            width: 40,
+            height: 6,
+            sprite_width: 3,
+            first_sample: 20,
+            sample_every: 40,
+        }
     }
 }
+impl CrtConfig {
+    pub fn sampled(&self, cycle: u32) -> bool {
+        cycle >= self.first_sample
+            && (cycle - self.first_sample).is_multiple_of(self.sample_every.max(1))
+    }
+
+    /** whether the sprite centred on `x` covers the pixel drawn during `cycle` */
+    pub fn lit(&self, cycle: u32, x: i32) -> bool {
+        let position = ((cycle as usize - 1) % self.width) as i32;
+        let start = x - (self.sprite_width as i32 - 1) / 2;
+        (start..start + self.sprite_width as i32).contains(&position)
+    }
 
-pub fn generator(input: &str) -> Input {
-    input
-        .lines()
-        .map(|line| {
-            let instruction = line.split_once(' ');
-            match instruction {
-                None => Instruction::Noop,
-                Some(v) => Instruction::Addx(v.1.parse().unwrap()),
+    pub fn signal_strength(&self, program: &Program) -> i32 {
+        let mut signal_strengths: Vec<i32> = Vec::new();
+
+        Vm::new(program).run(|tick| {
+            if self.sampled(tick.cycle) {
+                signal_strengths.push(tick.cycle as i32 * tick.registers[0]);
             }
-        })
-        .collect()
-}
+        });
 
-pub fn part1(input: &Input) -> i32 {
-    let mut processor = Processor::new();
-    let mut signal_strengths: Vec<i32> = Vec::new();
+        signal_strengths.iter().sum()
+    }
 
-    let mut instructions = input.iter();
+    /** anything drawn after the last row is dropped */
+    pub fn draw(&self, program: &Program) -> Screen {
+        let mut screen = Screen {
+            width: self.width,
+            height: self.height,
+            pixels: vec![false; self.width * self.height],
+        };
 
-    while instructions.len() > 0 || processor.instruction.is_some() {
-        if processor.blocking == 0 {
-            processor.start_processing(instructions.next());
-        }
+        Vm::new(program).run(|tick| {
+            let index = tick.cycle as usize - 1;
+            if index < screen.pixels.len() && self.lit(tick.cycle, tick.registers[0]) {
+                screen.pixels[index] = true;
+            }
+        });
 
-        if processor.cycle == 20 || ((processor.cycle - 20) % 40 == 0) {
-            signal_strengths.push(processor.cycle * processor.register);
-        }
+        screen
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Screen {
+    pub width: usize,
+    pub height: usize,
+    /** row by row, true when lit */
+    pub pixels: Vec<bool>,
+}
+impl Screen {
+    fn rows(&self) -> impl Iterator<Item = &[bool]> {
+        self.pixels.chunks(self.width.max(1))
+    }
+
+    pub fn to_ascii(&self, lit: char, dark: char) -> String {
+        self.rows()
+            .map(|row| {
+                row.iter()
+                    .map(|pixel| if *pixel { lit } else { dark })
+                    .collect::<String>()
+            })
+            .join("\n")
+    }
 
-        processor.tick();
-        if processor.blocking == 0 {
-            processor.finish_processing();
+    /** two rows per line of text, for a squarer picture in a terminal */
+    #[allow(dead_code)]
+    pub fn to_half_blocks(&self) -> String {
+        self.rows()
+            .chunks(2)
+            .into_iter()
+            .map(|mut pair| {
+                let top = pair.next().unwrap_or(&[]);
+                let bottom = pair.next().unwrap_or(&[]);
+                (0..self.width)
+                    .map(
+                        |x| match (top.get(x) == Some(&true), bottom.get(x) == Some(&true)) {
+                            (true, true) => '█',
+                            (true, false) => '▀',
+                            (false, true) => '▄',
+                            (false, false) => ' ',
+                        },
+                    )
+                    .collect::<String>()
+            })
+            .join("\n")
+    }
+
+    /** binary PBM, lit pixels are black */
+    #[allow(dead_code)]
+    pub fn write_pbm(&self, out: &mut impl io::Write) -> io::Result<()> {
+        write!(out, "P4\n{} {}\n", self.width, self.height)?;
+        for row in self.rows() {
+            // rows are packed 8 pixels to a byte, most significant bit first
+            let bytes = row
+                .chunks(8)
+                .map(|bits| {
+                    bits.iter()
+                        .enumerate()
+                        .fold(0u8, |byte, (i, bit)| byte | ((*bit as u8) << (7 - i)))
+                })
+                .collect_vec();
+            out.write_all(&bytes)?;
         }
+        out.flush()
     }
-    signal_strengths.iter().sum()
+}
+
+pub fn part1(input: &Input) -> i32 {
+    CrtConfig::default().signal_strength(input)
 }
 
 pub fn part2(input: &Input) -> String {
-    let mut processor = Processor::new();
-    let mut crt: Crt = vec![vec![String::from(" "); 40]; 6];
+    CrtConfig::default().draw(input).to_ascii('#', ' ')
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TraceRow {
+    pub cycle: u32,
+    pub instruction: String,
+    pub x_before: i32,
+    pub x_after: i32,
+    pub pixel: bool,
+}
 
-    let mut instructions = input.iter();
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Trace {
+    pub rows: Vec<TraceRow>,
+}
+impl Trace {
+    /** every cycle of a full run */
+    #[allow(dead_code)]
+    pub fn record(program: &Program) -> Self {
+        let mut debugger = Debugger::new(program);
+        while debugger.step().is_some() {}
+        debugger.trace
+    }
 
-    while instructions.len() > 0 || processor.instruction.is_some() {
-        if processor.blocking == 0 {
-            processor.start_processing(instructions.next());
+    #[allow(dead_code)]
+    pub fn cycles(&self, range: impl RangeBounds<u32>) -> Self {
+        Self {
+            rows: self
+                .rows
+                .iter()
+                .filter(|row| range.contains(&row.cycle))
+                .cloned()
+                .collect(),
         }
+    }
+
+    #[allow(dead_code)]
+    pub fn to_csv(&self) -> String {
+        std::iter::once(String::from("cycle,instruction,x_before,x_after,pixel"))
+            .chain(self.rows.iter().map(|row| {
+                format!(
+                    "{},{},{},{},{}",
+                    row.cycle, row.instruction, row.x_before, row.x_after, row.pixel
+                )
+            }))
+            .join("\n")
+    }
+
+    #[allow(dead_code)]
+    pub fn to_json(&self) -> String {
+        let rows = self
+            .rows
+            .iter()
+            .map(|row| {
+                json!({
+                    "cycle": row.cycle,
+                    "instruction": row.instruction,
+                    "x_before": row.x_before,
+                    "x_after": row.x_after,
+                    "pixel": row.pixel,
+                })
+            })
+            .collect_vec();
+        Value::Array(rows).to_string()
+    }
+}
 
-        let row = (processor.cycle - 1) / 40;
-        let position = (processor.cycle - 1) % 40;
-        let sprite = processor.register - 1..=processor.register + 1;
-        if sprite.contains(&position) {
-            crt[row as usize][position as usize] = "#".to_string();
+type Condition = Box<dyn Fn(&[i32]) -> bool>;
+
+/** checked before each cycle runs, against the registers it would run with */
+#[allow(dead_code)]
+pub enum Breakpoint {
+    Cycle(u32),
+    When(Condition),
+}
+
+/** steps the VM a cycle at a time, tracing as it goes */
+pub struct Debugger<'a> {
+    vm: Vm<'a>,
+    breakpoints: Vec<Breakpoint>,
+    stopped: Option<u32>,
+    /** only used to work out which pixels get drawn */
+    pub config: CrtConfig,
+    pub trace: Trace,
+}
+impl<'a> Debugger<'a> {
+    pub fn new(program: &'a Program) -> Self {
+        Self {
+            vm: Vm::new(program),
+            breakpoints: Vec::new(),
+            stopped: None,
+            config: CrtConfig::default(),
+            trace: Trace::default(),
         }
+    }
+
+    #[allow(dead_code)]
+    pub fn break_on(&mut self, breakpoint: Breakpoint) {
+        self.breakpoints.push(breakpoint);
+    }
 
-        processor.tick();
-        if processor.blocking == 0 {
-            processor.finish_processing();
+    #[allow(dead_code)]
+    pub fn registers(&self) -> &[i32] {
+        &self.vm.registers
+    }
+
+    pub fn step(&mut self) -> Option<&TraceRow> {
+        let tick = self.vm.step()?;
+        self.trace.rows.push(TraceRow {
+            cycle: tick.cycle,
+            instruction: self.vm.program.line(tick.pc),
+            x_before: tick.registers[0],
+            x_after: self.vm.registers[0],
+            pixel: self.config.lit(tick.cycle, tick.registers[0]),
+        });
+        self.trace.rows.last()
+    }
+
+    /** runs until a breakpoint, returning the cycle it stopped before, or None once the program ends */
+    #[allow(dead_code)]
+    pub fn resume(&mut self) -> Option<u32> {
+        while !self.vm.finished() {
+            let cycle = self.vm.cycle;
+            // don't stop twice in the same spot when resuming from a breakpoint
+            if self.stopped != Some(cycle) && self.hit() {
+                self.stopped = Some(cycle);
+                return self.stopped;
+            }
+            self.step();
         }
+        None
     }
 
-    convert(&crt)
+    fn hit(&self) -> bool {
+        self.breakpoints.iter().any(|breakpoint| match breakpoint {
+            Breakpoint::Cycle(cycle) => *cycle == self.vm.cycle,
+            Breakpoint::When(condition) => condition(&self.vm.registers),
+        })
+    }
 }
 
-fn convert(crt: &Crt) -> String {
-    crt.iter().map(|row| row.join("")).collect_vec().join("\n")
+/** the letters part2 draws */
+pub fn part2_ocr(input: &Input) -> Result<String, OcrError> {
+    ocr::decode(&part2(input))
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::error::located;
 
     const SAMPLE: &str = "addx 15
 addx -11
@@ -271,13 +635,113 @@ noop";
 
     #[test]
     fn test_part1() {
-        assert_eq!(part1(&generator(SAMPLE)), 13140);
+        assert_eq!(part1(&generator(SAMPLE).unwrap()), 13140);
+    }
+
+    #[test]
+    fn test_vm() {
+        let program = generator("noop\naddx 3\naddx -5").unwrap();
+        assert_eq!(program.to_string(), "noop\naddx 3\naddx -5");
+
+        let mut vm = Vm::new(&program);
+        let mut seen = Vec::new();
+        vm.run(|tick| seen.push((tick.cycle, tick.registers[0])));
+        assert_eq!(seen, vec![(1, 1), (2, 1), (3, 1), (4, 4), (5, 4)]);
+        assert_eq!(vm.registers, vec![-1]);
+
+        // a slower addx and a second register
+        let set = InstructionSet::standard()
+            .with_register("y", 0)
+            .with(Op {
+                name: "addx",
+                cycles: 3,
+                operands: 1,
+                execute: |registers, args| registers[0] += args[0],
+            })
+            .with(Op {
+                name: "swap",
+                cycles: 1,
+                operands: 0,
+                execute: |registers, _| registers.swap(0, 1),
+            });
+        let program = set.assemble("addx 3\nswap").unwrap();
+        let mut vm = Vm::new(&program);
+        vm.run(|_| {});
+        assert_eq!((vm.cycle, vm.registers.clone()), (5, vec![0, 4]));
+        assert!(set.assemble("addx").is_err());
+    }
+
+    #[test]
+    fn test_trace() {
+        let program = generator("noop\naddx 3\naddx -5").unwrap();
+        let trace = Trace::record(&program);
+        assert_eq!(trace.rows.len(), 5);
+        assert_eq!(
+            trace.cycles(2..=3).to_csv(),
+            "cycle,instruction,x_before,x_after,pixel
+2,addx 3,1,1,true
+3,addx 3,1,4,true"
+        );
+        assert_eq!(
+            trace.cycles(5..).to_json(),
+            r#"[{"cycle":5,"instruction":"addx -5","pixel":true,"x_after":-1,"x_before":4}]"#
+        );
+    }
+
+    #[test]
+    fn test_breakpoints() {
+        let program = generator(SAMPLE).unwrap();
+        let mut debugger = Debugger::new(&program);
+        debugger.break_on(Breakpoint::Cycle(20));
+        debugger.break_on(Breakpoint::When(Box::new(|registers| registers[0] < 0)));
+
+        assert_eq!(debugger.resume(), Some(20));
+        assert_eq!(debugger.registers(), &[21]);
+        assert_eq!(debugger.trace.rows.len(), 19);
+
+        // X first goes negative on cycle 210
+        assert_eq!(debugger.resume(), Some(210));
+        assert!(debugger.registers()[0] < 0);
+        assert_eq!(debugger.step().unwrap().cycle, 210);
+    }
+
+    #[test]
+    fn test_crt_config() {
+        let program = generator(SAMPLE).unwrap();
+        let config = CrtConfig {
+            width: 20,
+            height: 12,
+            sprite_width: 1,
+            first_sample: 10,
+            sample_every: 100,
+        };
+        // cycles 10, 110 and 210
+        assert_eq!(config.signal_strength(&program), 10 * 8 + 110 * 13 - 210);
+
+        let screen = config.draw(&program);
+        assert_eq!(screen.to_ascii('#', '.').lines().count(), 12);
+        assert_eq!(
+            screen.to_ascii('#', '.').lines().next(),
+            Some(".#...#..#...#....#..")
+        );
+
+        let default = CrtConfig::default().draw(&program);
+        assert_eq!(
+            default.to_half_blocks().lines().next(),
+            Some("██▄ ▀▀▄▄█▀  ██▄ ▀▀▄▄█▀  ██▄ ▀▀▄▄█▀  ██▄ ")
+        );
+
+        let mut image: Vec<u8> = Vec::new();
+        default.write_pbm(&mut image).unwrap();
+        // 40 pixels pack into 5 bytes a row, "##  ##  " is 0b11001100
+        assert_eq!(image.len(), b"P4\n40 6\n".len() + 5 * 6);
+        assert_eq!(image[b"P4\n40 6\n".len()], 0b11001100);
     }
 
     #[test]
     fn test_part2() {
         assert_eq!(
-            part2(&generator(SAMPLE)),
+            part2(&generator(SAMPLE).unwrap()),
             "##  ##  ##  ##  ##  ##  ##  ##  ##  ##  
 ###   ###   ###   ###   ###   ###   ### 
 ####    ####    ####    ####    ####    
@@ -285,5 +749,20 @@ noop";
 ######      ######      ######      ####
 #######       #######       #######     "
         );
+        // the sample draws stripes rather than letters
+        assert!(matches!(
+            part2_ocr(&generator(SAMPLE).unwrap()),
+            Err(OcrError::Glyph { column: 0, .. })
+        ));
+    }
+
+    #[test]
+    fn test_generator() {
+        assert_eq!(
+            located(generator("noop\naddx")),
+            "2:1 addx takes 1 operand(s)"
+        );
+        assert_eq!(located(generator("jmp 2")), "1:1 unknown instruction");
+        assert_eq!(located(generator("noop\naddx y")), "2:6 expected a number");
     }
 }
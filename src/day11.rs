@@ -1,143 +1,689 @@
-use std::fmt::Debug;
+use std::{
+    collections::HashMap,
+    fmt::{self, Debug},
+    str::FromStr,
+};
 
 use itertools::Itertools;
 
-pub struct Input<'a> {
+use crate::{bigint::BigUint, error::ParseError};
+
+const DAY: u8 = 11;
+
+pub struct Input {
     holding: Vec<u64>,
-    operation: (&'a str, &'a str),
-    test: (&'a str, &'a str),
+    operation: Expr,
+    divisor: u64,
     if_true: usize,
     if_false: usize,
 }
 
-pub struct Monkey {
-    holding: Vec<u64>,
-    operation: Box<dyn Fn(&u64) -> u64>,
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum BinOp {
+    Add,
+    Sub,
+    Mul,
+    Div,
+}
+
+/** right hand side of `new = ...` */
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Expr {
+    Old,
+    Literal(u64),
+    Binary(Box<Expr>, BinOp, Box<Expr>),
+}
+impl Expr {
+    /** None on overflow, underflow or dividing by zero */
+    pub fn eval<W: Worry>(&self, old: &W) -> Option<W> {
+        match self {
+            Expr::Old => Some(old.clone()),
+            Expr::Literal(value) => Some(W::from_u64(*value)),
+            Expr::Binary(left, op, right) => {
+                let (left, right) = (left.eval(old)?, right.eval(old)?);
+                match op {
+                    BinOp::Add => left.checked_add(&right),
+                    BinOp::Sub => left.checked_sub(&right),
+                    BinOp::Mul => left.checked_mul(&right),
+                    BinOp::Div => left.checked_div(&right),
+                }
+            }
+        }
+    }
+
+    /** only `+` and `*` give the same remainders when worry is kept modulo the divisors */
+    pub fn is_modular_safe(&self) -> bool {
+        match self {
+            Expr::Old | Expr::Literal(_) => true,
+            Expr::Binary(left, op, right) => {
+                matches!(op, BinOp::Add | BinOp::Mul)
+                    && left.is_modular_safe()
+                    && right.is_modular_safe()
+            }
+        }
+    }
+
+    /** `text` has to be a slice of `input` so errors point at the right spot */
+    pub fn parse(input: &str, text: &str) -> Result<Expr, ParseError> {
+        let tokens = tokenize(input, text)?;
+        let mut position = 0;
+        let expr = parse_sum(input, text, &tokens, &mut position)?;
+        match tokens.get(position) {
+            Some(token) => Err(ParseError::new(DAY, input, token, "unexpected token")),
+            None => Ok(expr),
+        }
+    }
+}
+impl fmt::Display for Expr {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Expr::Old => write!(f, "old"),
+            Expr::Literal(value) => write!(f, "{}", value),
+            Expr::Binary(left, op, right) => {
+                let symbol = match op {
+                    BinOp::Add => '+',
+                    BinOp::Sub => '-',
+                    BinOp::Mul => '*',
+                    BinOp::Div => '/',
+                };
+                // nested operations always get parentheses, so nothing depends on precedence
+                let side = |expr: &Expr| match expr {
+                    Expr::Binary(..) => format!("({})", expr),
+                    _ => expr.to_string(),
+                };
+                write!(f, "{} {} {}", side(left), symbol, side(right))
+            }
+        }
+    }
+}
+
+fn tokenize<'a>(input: &str, text: &'a str) -> Result<Vec<&'a str>, ParseError> {
+    let mut tokens = Vec::new();
+    let mut chars = text.char_indices().peekable();
+
+    while let Some((start, char)) = chars.next() {
+        match char {
+            ' ' | '\t' => {}
+            '+' | '-' | '*' | '/' | '(' | ')' => tokens.push(&text[start..start + 1]),
+            '0'..='9' | 'a'..='z' => {
+                let mut end = start + 1;
+                while let Some((index, next)) = chars.peek() {
+                    if !next.is_ascii_alphanumeric() {
+                        break;
+                    }
+                    end = index + next.len_utf8();
+                    chars.next();
+                }
+                tokens.push(&text[start..end]);
+            }
+            _ => {
+                return Err(ParseError::new(
+                    DAY,
+                    input,
+                    &text[start..start + char.len_utf8()],
+                    "unexpected character",
+                ))
+            }
+        }
+    }
+
+    Ok(tokens)
+}
+
+/** terms joined by + and - */
+fn parse_sum(
+    input: &str,
+    text: &str,
+    tokens: &[&str],
+    position: &mut usize,
+) -> Result<Expr, ParseError> {
+    let mut expr = parse_product(input, text, tokens, position)?;
+    while let Some(op) = tokens.get(*position).and_then(|token| match *token {
+        "+" => Some(BinOp::Add),
+        "-" => Some(BinOp::Sub),
+        _ => None,
+    }) {
+        *position += 1;
+        let right = parse_product(input, text, tokens, position)?;
+        expr = Expr::Binary(Box::new(expr), op, Box::new(right));
+    }
+    Ok(expr)
+}
+
+/** factors joined by * and / */
+fn parse_product(
+    input: &str,
+    text: &str,
+    tokens: &[&str],
+    position: &mut usize,
+) -> Result<Expr, ParseError> {
+    let mut expr = parse_factor(input, text, tokens, position)?;
+    while let Some(op) = tokens.get(*position).and_then(|token| match *token {
+        "*" => Some(BinOp::Mul),
+        "/" => Some(BinOp::Div),
+        _ => None,
+    }) {
+        *position += 1;
+        let right = parse_factor(input, text, tokens, position)?;
+        expr = Expr::Binary(Box::new(expr), op, Box::new(right));
+    }
+    Ok(expr)
+}
+
+fn parse_factor(
+    input: &str,
+    text: &str,
+    tokens: &[&str],
+    position: &mut usize,
+) -> Result<Expr, ParseError> {
+    let token = *tokens
+        .get(*position)
+        .ok_or_else(|| ParseError::new(DAY, input, &text[text.len()..], "expected a value"))?;
+    *position += 1;
+
+    match token {
+        "old" => Ok(Expr::Old),
+        "(" => {
+            let expr = parse_sum(input, text, tokens, position)?;
+            match tokens.get(*position) {
+                Some(&")") => {
+                    *position += 1;
+                    Ok(expr)
+                }
+                Some(other) => Err(ParseError::new(DAY, input, other, "expected `)`")),
+                None => Err(ParseError::new(
+                    DAY,
+                    input,
+                    &text[text.len()..],
+                    "expected `)`",
+                )),
+            }
+        }
+        _ => token
+            .parse()
+            .map(Expr::Literal)
+            .map_err(|_| ParseError::new(DAY, input, token, "expected `old` or a number")),
+    }
+}
+
+/** number type for worry levels, checked so nothing silently wraps */
+pub trait Worry: Clone + fmt::Display {
+    fn from_u64(value: u64) -> Self;
+    fn checked_add(&self, other: &Self) -> Option<Self>;
+    fn checked_sub(&self, other: &Self) -> Option<Self>;
+    fn checked_mul(&self, other: &Self) -> Option<Self>;
+    fn checked_div(&self, other: &Self) -> Option<Self>;
+    fn rem_u64(&self, divisor: u64) -> u64;
+    /** keep the level small when relief no longer does, a no-op unless modular */
+    fn reduce(&mut self, _cycle_length: u64) {}
+}
+
+macro_rules! primitive_worry {
+    ($type:ty) => {
+        impl Worry for $type {
+            fn from_u64(value: u64) -> Self {
+                value as $type
+            }
+            fn checked_add(&self, other: &Self) -> Option<Self> {
+                <$type>::checked_add(*self, *other)
+            }
+            fn checked_sub(&self, other: &Self) -> Option<Self> {
+                <$type>::checked_sub(*self, *other)
+            }
+            fn checked_mul(&self, other: &Self) -> Option<Self> {
+                <$type>::checked_mul(*self, *other)
+            }
+            fn checked_div(&self, other: &Self) -> Option<Self> {
+                <$type>::checked_div(*self, *other)
+            }
+            fn rem_u64(&self, divisor: u64) -> u64 {
+                (*self % divisor as $type) as u64
+            }
+        }
+    };
+}
+primitive_worry!(u64);
+primitive_worry!(u128);
+
+/** u64 kept modulo the product of every divisor, which no test can tell apart as long
+as operations only add and multiply, see `Expr::is_modular_safe` */
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct Modular(u64);
+impl fmt::Display for Modular {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.0)
+    }
+}
+impl Worry for Modular {
+    fn from_u64(value: u64) -> Self {
+        Modular(value)
+    }
+    fn checked_add(&self, other: &Self) -> Option<Self> {
+        self.0.checked_add(other.0).map(Modular)
+    }
+    fn checked_sub(&self, other: &Self) -> Option<Self> {
+        self.0.checked_sub(other.0).map(Modular)
+    }
+    fn checked_mul(&self, other: &Self) -> Option<Self> {
+        self.0.checked_mul(other.0).map(Modular)
+    }
+    fn checked_div(&self, other: &Self) -> Option<Self> {
+        self.0.checked_div(other.0).map(Modular)
+    }
+    fn rem_u64(&self, divisor: u64) -> u64 {
+        self.0 % divisor
+    }
+    fn reduce(&mut self, cycle_length: u64) {
+        self.0 %= cycle_length;
+    }
+}
+
+impl Worry for BigUint {
+    fn from_u64(value: u64) -> Self {
+        BigUint::from(value)
+    }
+    fn checked_add(&self, other: &Self) -> Option<Self> {
+        Some(self.add(other))
+    }
+    fn checked_sub(&self, other: &Self) -> Option<Self> {
+        BigUint::checked_sub(self, other)
+    }
+    fn checked_mul(&self, other: &Self) -> Option<Self> {
+        Some(self.mul(other))
+    }
+    fn checked_div(&self, other: &Self) -> Option<Self> {
+        BigUint::checked_div(self, other)
+    }
+    fn rem_u64(&self, divisor: u64) -> u64 {
+        self.div_rem_u64(divisor)
+            .map_or(0, |(_, remainder)| remainder)
+    }
+}
+
+/** what happens to worry levels after each inspection */
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Relief {
+    /** part 1, the level is divided (by 3) because the item wasn't damaged */
+    Divide(u64),
+    /** part 2, levels are only kept in check by the backend */
+    Disabled,
+}
+
+/** which `Worry` type a run uses */
+#[allow(dead_code)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Backend {
+    /** `Modular`, the same as `U64` while relief divides */
+    Modular,
+    U64,
+    U128,
+    Big,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Overflow {
+    pub monkey: usize,
+    /** 1-based like the puzzle */
+    pub round: u64,
+    /** worry level before the failed step */
+    pub item: String,
+    pub operation: String,
+}
+impl fmt::Display for Overflow {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "monkey {} overflowed in round {} on {} with old = {}",
+            self.monkey, self.round, self.operation, self.item
+        )
+    }
+}
+impl std::error::Error for Overflow {}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum WorryError {
+    Overflow(Overflow),
+    /** levels kept modulo the divisors go wrong once an operation subtracts or divides */
+    NotModular {
+        monkey: usize,
+        operation: String,
+    },
+    /** the top inspection counts multiply past a u64 */
+    Business {
+        top: usize,
+    },
+}
+impl From<Overflow> for WorryError {
+    fn from(overflow: Overflow) -> Self {
+        WorryError::Overflow(overflow)
+    }
+}
+impl fmt::Display for WorryError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            WorryError::Overflow(overflow) => write!(f, "{}", overflow),
+            WorryError::NotModular { monkey, operation } => write!(
+                f,
+                "monkey {} has new = {}, which can't be kept modulo the divisors",
+                monkey, operation
+            ),
+            WorryError::Business { top } => {
+                write!(f, "the top {} inspection counts overflow a u64", top)
+            }
+        }
+    }
+}
+impl std::error::Error for WorryError {}
+
+/** reducing modulo the divisors only happens without relief, so that's all that's checked */
+fn check_modular(input: &[Input], relief: Relief) -> Result<(), WorryError> {
+    if relief != Relief::Disabled {
+        return Ok(());
+    }
+    match input
+        .iter()
+        .position(|monkey| !monkey.operation.is_modular_safe())
+    {
+        Some(monkey) => Err(WorryError::NotModular {
+            monkey,
+            operation: input[monkey].operation.to_string(),
+        }),
+        None => Ok(()),
+    }
+}
+
+pub struct Monkey<W> {
+    holding: Vec<W>,
+    operation: Expr,
     divisor: u64,
     if_true: usize,
     if_false: usize,
     inspections: u64,
 }
-impl Monkey {
+impl<W: Worry> Monkey<W> {
     fn new(input: &Input) -> Self {
         Self {
-            holding: input.holding.clone(),
-            operation: {
-                let num: u64 = input.operation.1.parse().unwrap_or(0);
-                match input.operation.0 {
-                    "*" => match num {
-                        0 => Box::new(move |item| item.pow(2)),
-                        _ => Box::new(move |item| item * num),
-                    },
-                    "+" => Box::new(move |item| item + num),
-                    _ => panic!("unknown operation"),
-                }
-            },
-            divisor: input
-                .test
-                .1
-                .parse()
-                .unwrap_or_else(|_| panic!("{}", input.test.1)),
+            holding: input
+                .holding
+                .iter()
+                .map(|item| W::from_u64(*item))
+                .collect(),
+            operation: input.operation.clone(),
+            divisor: input.divisor,
             if_true: input.if_true,
             if_false: input.if_false,
             inspections: 0,
         }
     }
 
-    fn inspect(&mut self, large: bool, cycle_length: u64) {
-        for item in self.holding.as_mut_slice() {
-            *item = (self.operation)(item);
-            //reduce worry
-            if large {
-                *item %= cycle_length
-            } else {
-                *item /= 3
-            };
+    /** Err has the operation that failed and the level it started from */
+    fn worry(&self, item: &W, relief: Relief, cycle_length: u64) -> Result<W, (String, String)> {
+        let overflow = |operation: String| (operation, item.to_string());
+        let mut level = self
+            .operation
+            .eval(item)
+            .ok_or_else(|| overflow(format!("new = {}", self.operation)))?;
+        //reduce worry
+        match relief {
+            Relief::Divide(by) => {
+                level = level
+                    .checked_div(&W::from_u64(by))
+                    .ok_or_else(|| overflow(format!("relief / {}", by)))?
+            }
+            Relief::Disabled => level.reduce(cycle_length),
+        }
+        Ok(level)
+    }
+
+    fn inspect(&mut self, relief: Relief, cycle_length: u64) -> Result<(), (String, String)> {
+        for i in 0..self.holding.len() {
+            self.holding[i] = self.worry(&self.holding[i], relief, cycle_length)?;
             self.inspections += 1;
         }
+        Ok(())
+    }
+
+    fn target(&self, item: &W) -> usize {
+        match item.rem_u64(self.divisor) == 0 {
+            true => self.if_true,
+            false => self.if_false,
+        }
     }
 
     fn test(&self) -> Vec<usize> {
-        self.holding
-            .iter()
-            .map(|item| match item % self.divisor == 0 {
-                true => self.if_true,
-                false => self.if_false,
-            })
-            .collect()
+        self.holding.iter().map(|item| self.target(item)).collect()
     }
 }
-impl Debug for Monkey {
+impl<W: Worry> Debug for Monkey<W> {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
-        f.write_fmt(format_args!("{:?}", self.holding))
-    }
-}
-
-pub fn generator(input: &str) -> Vec<Input> {
-    input
-        .split("\n\n")
-        .map(|chunk| {
-            let mut lines = chunk.lines();
-            lines.next(); // don't need the monkey number as it aligns with the index
-            Input {
-                holding: lines
-                    .next()
-                    .unwrap()
-                    .split_once(": ")
-                    .unwrap()
-                    .1
-                    .split(", ")
-                    .map(|item| item.parse().unwrap_or_else(|item| panic!("{}", item)))
-                    .collect(),
-                operation: lines
-                    .next()
-                    .unwrap()
-                    .split_once("= old ")
-                    .unwrap()
-                    .1
-                    .split_once(' ')
-                    .unwrap(),
-                test: lines
-                    .next()
-                    .unwrap()
-                    .split_once(": ")
-                    .unwrap()
-                    .1
-                    .split_once(" by ")
-                    .unwrap(),
-                if_true: lines
-                    .next()
-                    .unwrap()
-                    .split_whitespace()
-                    .last()
-                    .unwrap()
-                    .parse()
-                    .unwrap_or_else(|item| panic!("{}", item)),
-                if_false: lines
-                    .next()
-                    .unwrap()
-                    .split_whitespace()
-                    .last()
-                    .unwrap()
-                    .parse()
-                    .unwrap_or_else(|item| panic!("{}", item)),
-            }
-        })
-        .collect()
-}
-
-pub fn rounds(mut monkies: Vec<Monkey>, rounds: u32, large: bool) -> u64 {
+        write!(f, "[{}]", self.holding.iter().join(", "))
+    }
+}
+
+pub fn generator(input: &str) -> Result<Vec<Input>, ParseError> {
+    let blocks = input
+        .lines()
+        .map(str::trim)
+        .group_by(|line| line.is_empty())
+        .into_iter()
+        .filter(|(blank, _)| !blank)
+        .map(|(_, block)| block.collect_vec())
+        .collect_vec();
+    if blocks.is_empty() {
+        return Err(ParseError::eof(DAY, input, "no monkeys"));
+    }
+
+    let notes = blocks
+        .iter()
+        .enumerate()
+        .map(|(index, block)| monkey(input, index, block))
+        .collect::<Result<Vec<_>, _>>()?;
+
+    // every monkey is known now, so the throws can be checked
+    for (index, (_, targets)) in notes.iter().enumerate() {
+        for text in targets {
+            let target: usize = number(input, text)?;
+            if target >= notes.len() {
+                return Err(ParseError::new(
+                    DAY,
+                    input,
+                    text,
+                    format!("there are only {} monkeys", notes.len()),
+                ));
+            }
+            if target == index {
+                return Err(ParseError::new(
+                    DAY,
+                    input,
+                    text,
+                    "a monkey can't throw to itself",
+                ));
+            }
+        }
+    }
+
+    Ok(notes.into_iter().map(|(monkey, _)| monkey).collect())
+}
+
+/** one block of notes, with the texts of both throw targets left to check */
+fn monkey<'a>(
+    input: &str,
+    index: usize,
+    block: &[&'a str],
+) -> Result<(Input, [&'a str; 2]), ParseError> {
+    let header = block[0];
+    let id = header
+        .strip_prefix("Monkey ")
+        .and_then(|rest| rest.strip_suffix(':'))
+        .ok_or_else(|| ParseError::new(DAY, input, header, "expected `Monkey N:`"))?;
+    if number::<usize>(input, id)? != index {
+        return Err(ParseError::new(
+            DAY,
+            input,
+            id,
+            format!("expected monkey {}", index),
+        ));
+    }
+
+    let mut notes: HashMap<&str, &str> = HashMap::new();
+    for line in &block[1..] {
+        let (label, value) = line
+            .split_once(':')
+            .ok_or_else(|| ParseError::new(DAY, input, line, "expected `label: value`"))?;
+        if !LABELS.contains(&label) {
+            return Err(ParseError::new(DAY, input, label, "unknown note"));
+        }
+        if notes.insert(label, value.trim()).is_some() {
+            return Err(ParseError::new(DAY, input, label, "repeated note"));
+        }
+    }
+    let note = |label: &str| {
+        notes
+            .get(label)
+            .copied()
+            .ok_or_else(|| ParseError::new(DAY, input, header, format!("missing `{}`", label)))
+    };
+    let phrase = |label: &str, prefix: &str| -> Result<&str, ParseError> {
+        let value = note(label)?;
+        value
+            .strip_prefix(prefix)
+            .map(str::trim_start)
+            .ok_or_else(|| ParseError::new(DAY, input, value, format!("expected `{}`", prefix)))
+    };
+
+    let items = note("Starting items")?;
+    let holding = match items {
+        "" => Vec::new(),
+        _ => items
+            .split(',')
+            .map(|item| number(input, item.trim()))
+            .collect::<Result<_, _>>()?,
+    };
+
+    let operation = Expr::parse(input, phrase("Operation", "new =")?)?;
+
+    let divisor_text = phrase("Test", "divisible by")?;
+    let divisor = number(input, divisor_text)?;
+    if divisor == 0 {
+        return Err(ParseError::new(
+            DAY,
+            input,
+            divisor_text,
+            "can't be divisible by 0",
+        ));
+    }
+
+    let targets = [
+        phrase("If true", "throw to monkey")?,
+        phrase("If false", "throw to monkey")?,
+    ];
+
+    Ok((
+        Input {
+            holding,
+            operation,
+            divisor,
+            if_true: number(input, targets[0])?,
+            if_false: number(input, targets[1])?,
+        },
+        targets,
+    ))
+}
+
+const LABELS: [&str; 5] = ["Starting items", "Operation", "Test", "If true", "If false"];
+
+fn number<T: FromStr>(input: &str, text: &str) -> Result<T, ParseError> {
+    text.parse()
+        .map_err(|_| ParseError::new(DAY, input, text, "expected a number"))
+}
+
+/** who holds what after a round, in the puzzle's words */
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Snapshot {
+    pub round: u32,
+    /** worry levels per monkey, as the backend prints them */
+    pub holding: Vec<Vec<String>>,
+    /** inspections per monkey so far */
+    pub inspections: Vec<u64>,
+}
+impl Snapshot {
+    fn take<W: Worry>(round: u32, monkies: &[Monkey<W>]) -> Self {
+        Self {
+            round,
+            holding: monkies
+                .iter()
+                .map(|monkey| monkey.holding.iter().map(W::to_string).collect())
+                .collect(),
+            inspections: monkies.iter().map(|monkey| monkey.inspections).collect(),
+        }
+    }
+}
+impl fmt::Display for Snapshot {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        writeln!(
+            f,
+            "After round {}, the monkeys are holding items with these worry levels:",
+            self.round
+        )?;
+        for (m, holding) in self.holding.iter().enumerate() {
+            writeln!(f, "Monkey {}: {}", m, holding.join(", "))?;
+        }
+        writeln!(f)?;
+        writeln!(f, "== After round {} ==", self.round)?;
+        for (m, inspections) in self.inspections.iter().enumerate() {
+            writeln!(f, "Monkey {} inspected items {} times.", m, inspections)?;
+        }
+        Ok(())
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Outcome {
+    /** per monkey */
+    pub inspections: Vec<u64>,
+    /** one for each requested round that was reached, in round order */
+    pub snapshots: Vec<Snapshot>,
+}
+
+/** product of the `top` highest inspection counts, the puzzle asks for the top 2, None when it overflows */
+pub fn monkey_business(inspections: &[u64], top: usize) -> Option<u64> {
+    inspections
+        .iter()
+        .sorted()
+        .rev()
+        .take(top)
+        .try_fold(1u64, |product, count| product.checked_mul(*count))
+}
+
+/** `snapshot_at` can include 0 for the starting position */
+pub fn rounds<W: Worry>(
+    mut monkies: Vec<Monkey<W>>,
+    rounds: u32,
+    relief: Relief,
+    snapshot_at: &[u32],
+) -> Result<Outcome, Overflow> {
     let cycle_length: u64 = monkies.iter().map(|monkey| monkey.divisor).product();
+    let mut snapshots = Vec::new();
+    if snapshot_at.contains(&0) {
+        snapshots.push(Snapshot::take(0, &monkies));
+    }
 
-    for _round in 0..rounds {
+    for round in 1..=rounds {
         for m in 0..monkies.len() {
-            monkies[m].inspect(large, cycle_length);
+            monkies[m]
+                .inspect(relief, cycle_length)
+                .map_err(|(operation, item)| Overflow {
+                    monkey: m,
+                    round: round.into(),
+                    item,
+                    operation,
+                })?;
 
             let throws = monkies[m]
                 .test()
                 .iter()
                 .enumerate()
-                .map(|(i, throw_to)| (*throw_to, monkies[m].holding[i]))
+                .map(|(i, throw_to)| (*throw_to, monkies[m].holding[i].clone()))
                 .collect_vec();
 
             for (throw_to, item) in throws {
@@ -145,28 +691,184 @@ pub fn rounds(mut monkies: Vec<Monkey>, rounds: u32, large: bool) -> u64 {
             }
             monkies[m].holding.clear();
         }
+
+        if snapshot_at.contains(&round) {
+            snapshots.push(Snapshot::take(round, &monkies));
+        }
     }
 
+    Ok(Outcome {
+        inspections: monkies.iter().map(|monkey| monkey.inspections).collect(),
+        snapshots,
+    })
+}
+
+/** `rounds` with the worry type picked at runtime */
+pub fn simulate(
+    input: &[Input],
+    count: u32,
+    relief: Relief,
+    backend: Backend,
+    snapshot_at: &[u32],
+) -> Result<Outcome, WorryError> {
+    fn monkies<W: Worry>(input: &[Input]) -> Vec<Monkey<W>> {
+        input.iter().map(Monkey::new).collect()
+    }
+
+    let outcome = match backend {
+        Backend::Modular => {
+            check_modular(input, relief)?;
+            rounds(monkies::<Modular>(input), count, relief, snapshot_at)
+        }
+        Backend::U64 => rounds(monkies::<u64>(input), count, relief, snapshot_at),
+        Backend::U128 => rounds(monkies::<u128>(input), count, relief, snapshot_at),
+        Backend::Big => rounds(monkies::<BigUint>(input), count, relief, snapshot_at),
+    };
+    Ok(outcome?)
+}
+
+/** one item followed on its own, nothing another item does changes where it goes */
+#[derive(Debug, Clone)]
+pub struct Journey {
+    /** the monkey holding the item at the start of each simulated round */
+    holders: Vec<usize>,
+    /** inspections of this item per monkey after each simulated round, from all zeros */
+    totals: Vec<Vec<u64>>,
+    /** first round of the loop and its length, once (monkey, worry) repeats */
+    cycle: Option<(usize, usize)>,
+}
+impl Journey {
+    fn follow(
+        monkies: &[Monkey<Modular>],
+        mut holder: usize,
+        mut item: Modular,
+        rounds: u64,
+        relief: Relief,
+        cycle_length: u64,
+    ) -> Result<Self, Overflow> {
+        let mut journey = Journey {
+            holders: Vec::new(),
+            totals: vec![vec![0; monkies.len()]],
+            cycle: None,
+        };
+        let mut seen = HashMap::new();
+
+        for round in 0..rounds {
+            if let Some(start) = seen.insert((holder, item), journey.holders.len()) {
+                journey.cycle = Some((start, journey.holders.len() - start));
+                break;
+            }
+            journey.holders.push(holder);
+
+            let mut totals = journey.totals.last().unwrap().clone();
+            // thrown to a later monkey it gets inspected again in the same round
+            loop {
+                let monkey = &monkies[holder];
+                item = monkey
+                    .worry(&item, relief, cycle_length)
+                    .map_err(|(operation, item)| Overflow {
+                        monkey: holder,
+                        round: round + 1,
+                        item,
+                        operation,
+                    })?;
+                totals[holder] += 1;
+
+                let next = monkey.target(&item);
+                let next_round = next <= holder;
+                holder = next;
+                if next_round {
+                    break;
+                }
+            }
+            journey.totals.push(totals);
+        }
+
+        Ok(journey)
+    }
+
+    /** rounds past the simulated ones map back into the loop */
+    fn simulated(&self, round: u64) -> (usize, u64) {
+        match self.cycle {
+            Some((start, length)) if round as usize >= start + length => {
+                let offset = round - start as u64;
+                (
+                    start + (offset % length as u64) as usize,
+                    offset / length as u64,
+                )
+            }
+            _ => (round as usize, 0),
+        }
+    }
+
+    /** the monkey holding the item at the start of `round`, counting from 0 */
+    #[allow(dead_code)]
+    pub fn holder(&self, round: u64) -> usize {
+        self.holders[self.simulated(round).0]
+    }
+
+    /** how often each monkey inspected the item in the first `rounds` rounds */
+    pub fn inspections(&self, rounds: u64) -> Vec<u64> {
+        let (round, loops) = self.simulated(rounds);
+        let per_loop = |m: usize| match self.cycle {
+            Some((start, length)) => self.totals[start + length][m] - self.totals[start][m],
+            None => 0,
+        };
+        (0..self.totals[0].len())
+            .map(|m| self.totals[round][m] + loops * per_loop(m))
+            .collect()
+    }
+}
+
+/** every item followed until its state repeats or `rounds` runs out */
+pub fn journeys(input: &[Input], rounds: u64, relief: Relief) -> Result<Vec<Journey>, WorryError> {
+    // a reduced level has to decide the item's future for a repeat to mean a loop
+    check_modular(input, relief)?;
+    let monkies: Vec<Monkey<Modular>> = input.iter().map(Monkey::new).collect();
+    let cycle_length: u64 = monkies.iter().map(|monkey| monkey.divisor).product();
+
     monkies
         .iter()
-        .map(|monkey| monkey.inspections)
-        .sorted()
-        .rev()
-        .take(2)
-        .product()
+        .enumerate()
+        .flat_map(|(m, monkey)| monkey.holding.iter().map(move |item| (m, *item)))
+        .map(|(m, item)| Journey::follow(&monkies, m, item, rounds, relief, cycle_length))
+        .collect::<Result<_, _>>()
+        .map_err(WorryError::from)
+}
+
+/** inspections per monkey after `rounds`, without simulating every round once items loop */
+pub fn extrapolate(input: &[Input], rounds: u64, relief: Relief) -> Result<Vec<u64>, WorryError> {
+    let mut inspections = vec![0; input.len()];
+    for journey in journeys(input, rounds, relief)? {
+        for (total, count) in inspections.iter_mut().zip(journey.inspections(rounds)) {
+            *total += count;
+        }
+    }
+    Ok(inspections)
 }
 
-pub fn part1(input: &[Input]) -> u64 {
-    rounds(input.iter().map(Monkey::new).collect(), 20, false)
+pub fn part1(input: &[Input]) -> Result<u64, WorryError> {
+    let outcome = simulate(input, 20, Relief::Divide(3), Backend::U64, &[])?;
+    business(&outcome.inspections)
 }
 
-pub fn part2(input: &[Input]) -> u64 {
-    rounds(input.iter().map(Monkey::new).collect(), 10_000, true)
+pub fn part2(input: &[Input]) -> Result<u64, WorryError> {
+    let outcome = simulate(input, 10_000, Relief::Disabled, Backend::Modular, &[])?;
+    business(&outcome.inspections)
+}
+
+pub fn part2_items(input: &[Input]) -> Result<u64, WorryError> {
+    business(&extrapolate(input, 10_000, Relief::Disabled)?)
+}
+
+fn business(inspections: &[u64]) -> Result<u64, WorryError> {
+    monkey_business(inspections, 2).ok_or(WorryError::Business { top: 2 })
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::error::located;
 
     const SAMPLE: &str = "Monkey 0:
 Starting items: 79, 98
@@ -196,13 +898,215 @@ Test: divisible by 17
     If true: throw to monkey 0
     If false: throw to monkey 1";
 
+    #[test]
+    fn test_expr() {
+        let parse = |text: &str| Expr::parse(text, text);
+        assert_eq!(parse("old * old").unwrap().eval(&7u64), Some(49));
+        assert_eq!(parse("3 + old * 2").unwrap().eval(&5u64), Some(13));
+        assert_eq!(parse("(3 + old) * 2").unwrap().eval(&5u64), Some(16));
+        assert_eq!(parse("100 / (old - 1)").unwrap().eval(&5u64), Some(25));
+        assert_eq!(
+            parse("10 - (old - 2) * 3").unwrap().to_string(),
+            "10 - ((old - 2) * 3)"
+        );
+
+        assert_eq!(parse("old - 10").unwrap().eval(&5u64), None);
+        assert_eq!(parse("old / (old - 5)").unwrap().eval(&5u64), None);
+        assert_eq!(parse("old * old").unwrap().eval(&u64::MAX), None);
+        assert!(parse("old * old")
+            .unwrap()
+            .eval(&BigUint::from(u64::MAX))
+            .is_some());
+
+        let error = parse("old * (old + 2").unwrap_err();
+        assert_eq!((error.column, error.message.as_str()), (15, "expected `)`"));
+        assert_eq!(parse("old % 2").unwrap_err().column, 5);
+    }
+
+    #[test]
+    fn test_generator() {
+        let crlf = SAMPLE
+            .lines()
+            .map(|line| format!("\t  {}  ", line))
+            .join("\r\n")
+            .replace("\r\n\t    \r\n", "\r\n\r\n\r\n");
+        assert_eq!(part1(&generator(&crlf).unwrap()), Ok(10605));
+
+        let error = |input: &str| located(generator(input));
+        let monkey = |id: usize, targets: (usize, usize)| {
+            format!(
+                "Monkey {}:\nStarting items: 1\nOperation: new = old\nTest: divisible by 2\n\
+                 If true: throw to monkey {}\nIf false: throw to monkey {}\n",
+                id, targets.0, targets.1
+            )
+        };
+        let two = |first, second| format!("{}\n{}", first, second);
+
+        assert_eq!(
+            error(&two(monkey(0, (1, 1)), monkey(2, (0, 0)))),
+            "8:8 expected monkey 1"
+        );
+        assert_eq!(
+            error(&two(monkey(0, (1, 2)), monkey(1, (0, 0)))),
+            "6:27 there are only 2 monkeys"
+        );
+        assert_eq!(
+            error(&two(monkey(0, (1, 1)), monkey(1, (0, 1)))),
+            "13:27 a monkey can't throw to itself"
+        );
+        assert_eq!(
+            error(&monkey(0, (1, 1)).replace("Test: divisible by 2\n", "")),
+            "1:1 missing `Test`"
+        );
+        assert_eq!(
+            error(&monkey(0, (1, 1)).replace("by 2", "by 0")),
+            "4:20 can't be divisible by 0"
+        );
+        assert_eq!(
+            error(&monkey(0, (1, 1)).replace("Operation", "Starting items")),
+            "3:1 repeated note"
+        );
+        assert_eq!(error(""), "1:1 no monkeys");
+    }
+
     #[test]
     fn test_part1() {
-        assert_eq!(part1(&generator(SAMPLE)), 10605);
+        assert_eq!(part1(&generator(SAMPLE).unwrap()), Ok(10605));
+    }
+
+    #[test]
+    fn test_backends() {
+        let input = generator(SAMPLE).unwrap();
+        let business = |relief, backend| {
+            simulate(&input, 20, relief, backend, &[])
+                .map(|outcome| monkey_business(&outcome.inspections, 2).unwrap())
+        };
+        for backend in [Backend::Modular, Backend::U128, Backend::Big] {
+            assert_eq!(business(Relief::Divide(3), backend), Ok(10605));
+        }
+
+        // without relief the sample passes u64 in round 13 but still fits in u128
+        for backend in [Backend::Modular, Backend::U128, Backend::Big] {
+            assert_eq!(business(Relief::Disabled, backend), Ok(99 * 103));
+        }
+        let overflow = business(Relief::Disabled, Backend::U64).unwrap_err();
+        assert_eq!(
+            overflow.to_string(),
+            "monkey 0 overflowed in round 13 on new = old * 19 with old = 13988703546165100909"
+        );
+    }
+
+    #[test]
+    fn test_not_modular() {
+        let halving = SAMPLE.replace("old + 6", "old / 2");
+        let input = generator(&halving).unwrap();
+        assert!(!input[1].operation.is_modular_safe());
+        assert!(input[2].operation.is_modular_safe());
+
+        let business = |relief, backend| {
+            simulate(&input, 20, relief, backend, &[])
+                .map(|outcome| monkey_business(&outcome.inspections, 2).unwrap())
+        };
+        assert_eq!(
+            business(Relief::Disabled, Backend::Modular),
+            Err(WorryError::NotModular {
+                monkey: 1,
+                operation: "old / 2".to_string()
+            })
+        );
+        assert!(business(Relief::Disabled, Backend::Big).is_ok());
+        assert!(matches!(
+            extrapolate(&input, 1_000_000, Relief::Disabled),
+            Err(WorryError::NotModular { monkey: 1, .. })
+        ));
+        assert_eq!(part2_items(&input), part2(&input));
+        assert_eq!(
+            extrapolate(&input, 20, Relief::Divide(3)).unwrap(),
+            simulate(&input, 20, Relief::Divide(3), Backend::Big, &[])
+                .unwrap()
+                .inspections
+        );
+        // relief keeps the levels exact, so nothing gets reduced
+        assert_eq!(
+            business(Relief::Divide(3), Backend::Modular),
+            business(Relief::Divide(3), Backend::Big)
+        );
+    }
+
+    #[test]
+    fn test_snapshots() {
+        let input = generator(SAMPLE).unwrap();
+        let outcome = simulate(&input, 20, Relief::Divide(3), Backend::U64, &[1, 20]).unwrap();
+        assert_eq!(
+            outcome.snapshots[0].to_string(),
+            "After round 1, the monkeys are holding items with these worry levels:
+Monkey 0: 20, 23, 27, 26
+Monkey 1: 2080, 25, 167, 207, 401, 1046
+Monkey 2: 
+Monkey 3: 
+
+== After round 1 ==
+Monkey 0 inspected items 2 times.
+Monkey 1 inspected items 4 times.
+Monkey 2 inspected items 3 times.
+Monkey 3 inspected items 5 times.
+"
+        );
+        assert_eq!(
+            outcome.snapshots[1].holding[0],
+            vec!["10", "12", "14", "26", "34"]
+        );
+        assert_eq!(outcome.snapshots[1].inspections, outcome.inspections);
+        assert_eq!(monkey_business(&outcome.inspections, 1), Some(105));
+        assert_eq!(
+            monkey_business(&outcome.inspections, 3),
+            Some(105 * 101 * 95)
+        );
+
+        let outcome =
+            simulate(&input, 1000, Relief::Disabled, Backend::Modular, &[0, 1000]).unwrap();
+        assert_eq!(
+            outcome.snapshots[0].holding[1],
+            vec!["54", "65", "75", "74"]
+        );
+        assert_eq!(
+            outcome.snapshots[1].inspections,
+            vec![5204, 4792, 199, 5192]
+        );
     }
 
     #[test]
     fn test_part2() {
-        assert_eq!(part2(&generator(SAMPLE)), 2713310158);
+        assert_eq!(part2(&generator(SAMPLE).unwrap()), Ok(2713310158));
+        assert_eq!(part2_items(&generator(SAMPLE).unwrap()), Ok(2713310158));
+    }
+
+    #[test]
+    fn test_extrapolate() {
+        let input = generator(SAMPLE).unwrap();
+        assert_eq!(
+            extrapolate(&input, 20, Relief::Divide(3)),
+            Ok(vec![101, 95, 7, 105])
+        );
+        assert_eq!(
+            extrapolate(&input, 10_000, Relief::Disabled),
+            Ok(vec![52166, 47830, 1938, 52013])
+        );
+
+        // checked against a plain simulation
+        assert_eq!(
+            extrapolate(&input, 100_000, Relief::Disabled),
+            Ok(vec![521753, 478243, 19332, 520194])
+        );
+
+        // every sample item loops long before a billion rounds
+        let journeys = journeys(&input, 1_000_000_000, Relief::Disabled).unwrap();
+        assert!(journeys.iter().all(|journey| journey.cycle.is_some()));
+        let billion = extrapolate(&input, 1_000_000_000, Relief::Disabled).unwrap();
+        assert_eq!(billion, vec![5217653496, 4782346500, 193256586, 5202028499]);
+        assert_eq!(monkey_business(&billion, 1), Some(5217653496));
+        assert_eq!(monkey_business(&billion, 2), None);
+        assert_eq!(journeys[0].holder(0), 0);
+        assert_eq!(journeys[0].holder(1), 1);
     }
 }
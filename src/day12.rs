@@ -1,25 +1,77 @@
+use std::{cell::Cell, collections::VecDeque, io};
+
 use grid::*;
 use itertools::Itertools;
-use pathfinding::{
-    num_traits::ToPrimitive,
-    prelude::{astar, bfs, dijkstra},
+use pathfinding::prelude::{astar, bfs, dijkstra};
+
+use crate::{
+    error::ParseError,
+    geom::{Direction as Step, Point},
 };
 
+const DAY: u8 = 12;
+
 type Input = Grid<i16>;
 type BfsNeighbor = Coord;
-type DijkstraNeighbor = (Coord, i16);
-
-#[derive(Clone, Debug, Eq, Hash, Ord, PartialEq, PartialOrd)]
-struct Coord {
-    x: usize,
-    y: usize,
-}
+type DijkstraNeighbor = (Coord, u32);
+type Coord = Point<usize>;
 
+/** Up walks from S toward E, Down walks backwards from E */
 enum Direction {
     Up,
     Down,
 }
 
+/** which steps between neighbouring cells are allowed and what they cost */
+pub trait Movement {
+    /** None when the step isn't allowed, `delta` is positive when climbing */
+    fn cost(&self, delta: i16) -> Option<u32>;
+
+    fn diagonals(&self) -> bool {
+        false
+    }
+}
+
+/** the puzzle's rule, climb at most one, drop any distance, every step costs 1 */
+pub struct Puzzle;
+impl Movement for Puzzle {
+    fn cost(&self, delta: i16) -> Option<u32> {
+        (delta <= 1).then_some(1)
+    }
+}
+
+/** a rule built from limits and a cost per elevation change */
+#[allow(dead_code)]
+pub struct Terrain {
+    pub max_climb: i16,
+    pub max_descent: i16,
+    /** cost of a step given the elevation change */
+    pub cost: fn(i16) -> u32,
+    pub diagonals: bool,
+}
+impl Default for Terrain {
+    /** the same as `Puzzle` */
+    fn default() -> Self {
+        Self {
+            max_climb: 1,
+            max_descent: i16::MAX,
+            cost: |_| 1,
+            diagonals: false,
+        }
+    }
+}
+impl Movement for Terrain {
+    fn cost(&self, delta: i16) -> Option<u32> {
+        (-self.max_descent..=self.max_climb)
+            .contains(&delta)
+            .then(|| (self.cost)(delta))
+    }
+
+    fn diagonals(&self) -> bool {
+        self.diagonals
+    }
+}
+
 struct Map {
     grid: Grid<i16>,
     start: Coord,
@@ -31,7 +83,7 @@ impl Map {
             for y in 0..input.rows() {
                 let find = input.iter_row(y).position(|&x| x == char as i16);
                 if let Some(x) = find {
-                    return Some(Coord { x, y });
+                    return Some(Point::new(x, y));
                 }
             }
             None
@@ -47,134 +99,454 @@ impl Map {
     }
 
     fn bfs_neighbors(&self, position: &Coord, direction: Direction) -> Vec<BfsNeighbor> {
-        let neighbors: Vec<BfsNeighbor> = Vec::new();
-        let store = |coord: Coord| coord;
-        self.eligible_neighbors(position, neighbors, store, direction)
+        self.moves(position, &Puzzle, direction)
+            .into_iter()
+            .map(|(coord, _)| coord)
+            .collect()
     }
 
-    fn dijkstra_neighbors(&self, position: &Coord, direction: Direction) -> Vec<DijkstraNeighbor> {
-        let neighbors: Vec<DijkstraNeighbor> = Vec::new();
-        let store = |coord: Coord| (coord, 1);
-        self.eligible_neighbors(position, neighbors, store, direction)
+    fn dijkstra_neighbors(
+        &self,
+        position: &Coord,
+        rule: &impl Movement,
+        direction: Direction,
+    ) -> Vec<DijkstraNeighbor> {
+        self.moves(position, rule, direction)
     }
 
-    fn eligible_neighbors<T>(
+    /** the steps `rule` allows out of `position`, or into it when going Down from E */
+    fn moves(
         &self,
         position: &Coord,
-        mut neighbors: Vec<T>,
-        store: fn(Coord) -> T,
+        rule: &impl Movement,
         direction: Direction,
-    ) -> Vec<T> {
-        let current_elevation = self.grid.get(position.y, position.x).unwrap();
-
-        let direction = match direction {
-            Direction::Up => up,
-            Direction::Down => down,
+    ) -> Vec<(Coord, u32)> {
+        let current_elevation = self.grid[position.y][position.x];
+        let neighbors = match rule.diagonals() {
+            true => position.neighbors8().collect_vec(),
+            false => position.neighbors4().collect_vec(),
         };
 
-        let mut check_neighbor = |coord: Coord| {
-            if let Some(elevation) = self.grid.get(coord.y, coord.x) {
-                if direction(elevation, current_elevation) {
-                    neighbors.push(store(coord))
+        neighbors
+            .into_iter()
+            .filter_map(|coord| {
+                // anything past the bottom/right edge is filtered by grid.get
+                let elevation = *self.grid.get(coord.y, coord.x)?;
+                let delta = match direction {
+                    Direction::Up => elevation - current_elevation,
+                    Direction::Down => current_elevation - elevation,
+                };
+                Some((coord, rule.cost(delta)?))
+            })
+            .collect()
+    }
+
+    /** steps from every cell to E, walking the climb rule backwards from E */
+    fn distances_to_end(&self) -> Grid<Option<u32>> {
+        let mut distances = Grid::init(self.grid.rows(), self.grid.cols(), None);
+        distances[self.end.y][self.end.x] = Some(0);
+        let mut queue = VecDeque::from([self.end]);
+
+        while let Some(position) = queue.pop_front() {
+            let distance = distances[position.y][position.x].unwrap();
+            for neighbor in self.bfs_neighbors(&position, Direction::Down) {
+                let cell = &mut distances[neighbor.y][neighbor.x];
+                if cell.is_none() {
+                    *cell = Some(distance + 1);
+                    queue.push_back(neighbor);
                 }
             }
+        }
+
+        distances
+    }
+
+    /** a lower bound on the cost from `position` to E, never more than the real cost */
+    fn heuristic(&self, position: &Coord, bounds: &Bounds, heuristic: Heuristic) -> u32 {
+        let (dx, dy) = (
+            self.end.x.abs_diff(position.x) as u32,
+            self.end.y.abs_diff(position.y) as u32,
+        );
+        // a diagonal step covers both axes at once
+        let steps = match bounds.diagonals {
+            true => dx.max(dy),
+            false => dx + dy,
         };
+        let gap = (self.grid[self.end.y][self.end.x] - self.grid[position.y][position.x]).max(0);
+
+        bounds.min_cost
+            * match heuristic {
+                Heuristic::Zero => 0,
+                Heuristic::Manhattan => steps,
+                Heuristic::ElevationGap => match bounds.max_climb {
+                    climb if climb > 0 => steps.max((gap as u32).div_ceil(climb as u32)),
+                    _ => steps,
+                },
+            }
+    }
+}
+
+pub fn generator(input: &str) -> Result<Input, ParseError> {
+    let mut grid = Grid::new(0, 0);
 
-        if position.y > 0 {
-            check_neighbor(Coord {
-                y: position.y - 1, // up
-                x: position.x,
-            });
+    for line in input.lines() {
+        if let Some((index, char)) = line
+            .char_indices()
+            .find(|(_, char)| !matches!(char, 'a'..='z' | 'S' | 'E'))
+        {
+            return Err(ParseError::new(
+                DAY,
+                input,
+                &line[index..index + char.len_utf8()],
+                "expected an elevation",
+            ));
         }
-        if position.y < self.grid.rows() - 1 {
-            check_neighbor(Coord {
-                y: position.y + 1, // down
-                x: position.x,
-            });
+        if grid.cols() > 0 && line.len() != grid.cols() {
+            return Err(ParseError::new(
+                DAY,
+                input,
+                line,
+                "rows must be the same width",
+            ));
         }
-        if position.x > 0 {
-            check_neighbor(Coord {
-                y: position.y,
-                x: position.x - 1, // left
-            });
+        grid.push_row(line.chars().map(|char| char as i16).collect_vec());
+    }
+
+    for marker in ['S', 'E'] {
+        let count = grid.iter().filter(|&&cell| cell == marker as i16).count();
+        if count != 1 {
+            return Err(ParseError::eof(
+                DAY,
+                input,
+                format!("expected exactly one `{}`, found {}", marker, count),
+            ));
         }
-        if position.x < self.grid.cols() - 1 {
-            check_neighbor(Coord {
-                y: position.y,
-                x: position.x + 1, // right
-            });
+    }
+
+    Ok(grid)
+}
+
+/** every cell from S to E along a shortest climb, both ends included */
+pub fn route(input: &Input) -> Option<Vec<Coord>> {
+    let map = Map::new(input);
+
+    bfs(
+        &map.start,
+        |coord| map.bfs_neighbors(coord, Direction::Up),
+        |coord| map.end.eq(coord),
+    )
+}
+
+/** the route drawn over the heightmap like the puzzle does, `.` off the route */
+#[allow(dead_code)]
+pub fn render(input: &Input, route: &[Coord]) -> String {
+    draw(input, route, false)
+}
+
+/** `render` with each cell's background shaded by elevation, for terminals */
+#[allow(dead_code)]
+pub fn render_ansi(input: &Input, route: &[Coord]) -> String {
+    draw(input, route, true)
+}
+
+fn draw(input: &Input, route: &[Coord], colour: bool) -> String {
+    let map = Map::new(input);
+    let mut symbols = Grid::init(map.grid.rows(), map.grid.cols(), '.');
+    for (from, to) in route.iter().tuple_windows() {
+        if let Some(step) = Step::between(from, to) {
+            symbols[from.y][from.x] = step.arrow();
         }
+    }
+    if let Some(last) = route.last() {
+        symbols[last.y][last.x] = 'E';
+    }
 
-        neighbors
+    (0..map.grid.rows())
+        .map(|y| {
+            let row: String = (0..map.grid.cols())
+                .map(|x| {
+                    let symbol = symbols[y][x];
+                    if !colour {
+                        return symbol.to_string();
+                    }
+                    // 24 steps of the greyscale ramp, black at `a`
+                    let shade = 232 + (map.grid[y][x] - b'a' as i16) * 23 / 25;
+                    let text = if shade > 243 { 16 } else { 255 };
+                    format!("\x1b[48;5;{};38;5;{}m{}", shade, text, symbol)
+                })
+                .collect();
+            match colour {
+                true => format!("{}\x1b[0m", row),
+                false => row,
+            }
+        })
+        .join("\n")
+}
+
+/** how far every cell is from E, None where E can't be reached */
+pub struct DistanceField {
+    pub distances: Grid<Option<u32>>,
+    map: Map,
+}
+impl DistanceField {
+    pub fn new(input: &Input) -> Self {
+        let map = Map::new(input);
+        Self {
+            distances: map.distances_to_end(),
+            map,
+        }
+    }
+
+    pub fn get(&self, coord: &Coord) -> Option<u32> {
+        self.distances.get(coord.y, coord.x).copied().flatten()
+    }
+
+    fn cells(&self) -> impl Iterator<Item = Coord> + '_ {
+        (0..self.distances.rows())
+            .flat_map(|y| (0..self.distances.cols()).map(move |x| Point::new(x, y)))
+    }
+
+    /** connected patches of cells that can't reach E, in reading order */
+    #[allow(dead_code)]
+    pub fn unreachable(&self) -> Vec<Vec<Coord>> {
+        let mut seen = Grid::init(self.distances.rows(), self.distances.cols(), false);
+        let mut regions = Vec::new();
+
+        for cell in self.cells() {
+            if seen[cell.y][cell.x] || self.get(&cell).is_some() {
+                continue;
+            }
+            seen[cell.y][cell.x] = true;
+            let mut region = Vec::new();
+            let mut stack = vec![cell];
+            while let Some(position) = stack.pop() {
+                region.push(position);
+                for neighbor in position.neighbors4() {
+                    let open = self.distances.get(neighbor.y, neighbor.x) == Some(&None);
+                    if open && !seen[neighbor.y][neighbor.x] {
+                        seen[neighbor.y][neighbor.x] = true;
+                        stack.push(neighbor);
+                    }
+                }
+            }
+            region.sort_by_key(|coord| (coord.y, coord.x));
+            regions.push(region);
+        }
+
+        regions
+    }
+
+    /** distance from each possible start (elevation `a`) that can reach E */
+    fn starts(&self) -> impl Iterator<Item = (Coord, u32)> + '_ {
+        self.cells()
+            .filter(|cell| self.map.grid[cell.y][cell.x] == b'a' as i16)
+            .filter_map(|cell| Some((cell, self.get(&cell)?)))
     }
 
+    /** the first in reading order on ties */
+    pub fn nearest_start(&self) -> Option<(Coord, u32)> {
+        self.starts().min_by_key(|(_, distance)| *distance)
+    }
+
+    /** the last in reading order on ties */
     #[allow(dead_code)]
-    fn heuristic(&self, position: &Coord) -> i16 {
-        let a = self.end.y.abs_diff(position.y);
-        let b = self.end.x.abs_diff(position.x);
-        // an actual use for the pythagorean theory lol
-        f32::sqrt((a.pow(2) + b.pow(2)) as f32).to_i16().unwrap()
+    pub fn farthest_start(&self) -> Option<(Coord, u32)> {
+        self.starts().max_by_key(|(_, distance)| *distance)
+    }
+
+    /** binary PPM, white at E fading to blue at the farthest cell, unreachable in red */
+    #[allow(dead_code)]
+    pub fn write_ppm(&self, out: &mut impl io::Write) -> io::Result<()> {
+        let farthest = self.distances.iter().flatten().max().copied().unwrap_or(0);
+
+        write!(
+            out,
+            "P6\n{} {}\n255\n",
+            self.distances.cols(),
+            self.distances.rows()
+        )?;
+        for distance in self.distances.iter() {
+            let color = match distance {
+                Some(distance) => {
+                    let fade = (255 * distance / farthest.max(1)) as u8;
+                    [255 - fade, 255 - fade, 255]
+                }
+                None => [200, 0, 0],
+            };
+            out.write_all(&color)?;
+        }
+        out.flush()
     }
 }
 
-fn up(elevation: &i16, current_elevation: &i16) -> bool {
-    elevation - current_elevation < 2
+/** the elevation of a cell as a letter, with S counting as `a` and E as `z` */
+pub fn elevation(input: &Input, coord: &Coord) -> char {
+    match input[coord.y][coord.x] as u8 {
+        b'S' => 'a',
+        b'E' => 'z',
+        other => other as char,
+    }
 }
 
-fn down(elevation: &i16, current_elevation: &i16) -> bool {
-    elevation - current_elevation > -2
+/** cells at `elevation` in reading order, `S` or `E` for just that cell */
+pub fn at_elevation(input: &Input, elevation_or_marker: char) -> Vec<Coord> {
+    region(
+        input,
+        &Point::new(0, 0),
+        &Point::new(input.cols(), input.rows()),
+    )
+    .into_iter()
+    .filter(|coord| match elevation_or_marker {
+        'S' | 'E' => input[coord.y][coord.x] == elevation_or_marker as i16,
+        _ => elevation(input, coord) == elevation_or_marker,
+    })
+    .collect()
 }
 
-pub fn generator(input: &str) -> Input {
-    let mut grid = Grid::new(0, 0);
+/** cells inside the box, inclusive on both corners and clipped to the map */
+pub fn region(input: &Input, min: &Coord, max: &Coord) -> Vec<Coord> {
+    (min.y..=max.y.min(input.rows().saturating_sub(1)))
+        .flat_map(|y| {
+            (min.x..=max.x.min(input.cols().saturating_sub(1))).map(move |x| Point::new(x, y))
+        })
+        .collect()
+}
 
-    for line in input.lines() {
-        grid.push_row(line.chars().map(|char| char as i16).collect_vec());
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Hike {
+    pub start: Coord,
+    pub goal: Coord,
+    /** both ends included */
+    pub path: Vec<Coord>,
+}
+impl Hike {
+    pub fn steps(&self) -> usize {
+        self.path.len() - 1
     }
-
-    grid
 }
 
-pub fn part1(input: &Input) -> usize {
+/** shortest climb from any of `starts` to any cell `goal` accepts */
+pub fn hike(input: &Input, starts: &[Coord], goal: impl Fn(&Coord) -> bool) -> Option<Hike> {
     let map = Map::new(input);
 
+    // None stands in front of every start, so a single search covers them all
     let path = bfs(
-        &map.start,
-        |coord| map.bfs_neighbors(coord, Direction::Up),
-        |coord| map.end.eq(coord),
-    )
-    .unwrap_or_else(|| panic!("no path found"));
+        &None,
+        |node: &Option<Coord>| match node {
+            None => starts.iter().copied().map(Some).collect_vec(),
+            Some(coord) => map
+                .bfs_neighbors(coord, Direction::Up)
+                .into_iter()
+                .map(Some)
+                .collect_vec(),
+        },
+        |node| node.as_ref().is_some_and(&goal),
+    )?;
+
+    let path = path.into_iter().flatten().collect_vec();
+    Some(Hike {
+        start: path[0],
+        goal: *path.last().unwrap(),
+        path,
+    })
+}
+
+pub fn part1(input: &Input) -> usize {
+    let path = route(input).unwrap_or_else(|| panic!("no path found"));
 
     path.len() - 1
 }
 
-#[allow(dead_code)]
-pub fn part1_dijkstra(input: &Input) -> i16 {
+/** cheapest way from S to E under `rule`, and what it costs */
+pub fn cheapest(input: &Input, rule: &impl Movement) -> Option<(Vec<Coord>, u32)> {
     let map = Map::new(input);
 
-    let path = dijkstra(
+    dijkstra(
         &map.start,
-        |coord| map.dijkstra_neighbors(coord, Direction::Up),
+        |coord| map.dijkstra_neighbors(coord, rule, Direction::Up),
         |coord| map.end.eq(coord),
     )
-    .unwrap_or_else(|| panic!("no path found"));
+}
+
+#[allow(dead_code)]
+pub fn part1_dijkstra(input: &Input) -> u32 {
+    let path = cheapest(input, &Puzzle).unwrap_or_else(|| panic!("no path found"));
 
     path.1
 }
 
+/** estimates for A*, each one admissible for any `Movement` */
 #[allow(dead_code)]
-pub fn part1_astar(input: &Input) -> i16 {
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Heuristic {
+    /** plain Dijkstra */
+    Zero,
+    /** grid distance, Chebyshev when the rule allows diagonals */
+    Manhattan,
+    /** grid distance or the climbs still needed to reach E's height, whichever is more */
+    ElevationGap,
+}
+
+/** what `rule` allows at best, found by trying every elevation change */
+struct Bounds {
+    min_cost: u32,
+    max_climb: i16,
+    diagonals: bool,
+}
+impl Bounds {
+    fn new(rule: &impl Movement) -> Self {
+        let span = (b'z' - b'a') as i16;
+        let allowed = (-span..=span)
+            .filter_map(|delta| Some((delta, rule.cost(delta)?)))
+            .collect_vec();
+        Self {
+            min_cost: allowed.iter().map(|(_, cost)| *cost).min().unwrap_or(0),
+            max_climb: allowed.iter().map(|(delta, _)| *delta).max().unwrap_or(0),
+            diagonals: rule.diagonals(),
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Search {
+    pub path: Vec<Coord>,
+    pub cost: u32,
+    /** nodes whose neighbors were generated, to compare heuristics */
+    pub expanded: usize,
+}
+
+/** A* from S to E, Err has the expansion count when E can't be reached */
+pub fn search(input: &Input, rule: &impl Movement, heuristic: Heuristic) -> Result<Search, usize> {
     let map = Map::new(input);
+    let bounds = Bounds::new(rule);
+    let expanded = Cell::new(0);
 
-    let path = astar(
+    let found = astar(
         &map.start,
-        |coord| map.dijkstra_neighbors(coord, Direction::Up),
-        |coord| map.heuristic(coord),
+        |coord| {
+            expanded.set(expanded.get() + 1);
+            map.dijkstra_neighbors(coord, rule, Direction::Up)
+        },
+        |coord| map.heuristic(coord, &bounds, heuristic),
         |coord| map.end.eq(coord),
-    )
-    .unwrap_or_else(|| panic!("no path found"));
+    );
+
+    match found {
+        Some((path, cost)) => Ok(Search {
+            path,
+            cost,
+            expanded: expanded.get(),
+        }),
+        None => Err(expanded.get()),
+    }
+}
 
-    path.1
+#[allow(dead_code)]
+pub fn part1_astar(input: &Input) -> u32 {
+    let found =
+        search(input, &Puzzle, Heuristic::ElevationGap).unwrap_or_else(|_| panic!("no path found"));
+
+    found.cost
 }
 
 pub fn part2(input: &Input) -> usize {
@@ -190,13 +562,29 @@ pub fn part2(input: &Input) -> usize {
     path.len() - 1
 }
 
+pub fn part2_field(input: &Input) -> u32 {
+    DistanceField::new(input)
+        .nearest_start()
+        .unwrap_or_else(|| panic!("no path found"))
+        .1
+}
+
+pub fn part2_hike(input: &Input) -> usize {
+    let end = at_elevation(input, 'E');
+    hike(input, &at_elevation(input, 'a'), |coord| {
+        end.contains(coord)
+    })
+    .unwrap_or_else(|| panic!("no path found"))
+    .steps()
+}
+
 #[allow(dead_code)]
-pub fn part2_dijkstra(input: &Input) -> i16 {
+pub fn part2_dijkstra(input: &Input) -> u32 {
     let map = Map::new(input);
 
     let path = dijkstra(
         &map.end,
-        |coord| map.dijkstra_neighbors(coord, Direction::Down),
+        |coord| map.dijkstra_neighbors(coord, &Puzzle, Direction::Down),
         |coord| map.grid[coord.y][coord.x] == b'a' as i16,
     )
     .unwrap_or_else(|| panic!("no path found"));
@@ -207,6 +595,7 @@ pub fn part2_dijkstra(input: &Input) -> i16 {
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::error::located;
 
     const SAMPLE: &str = "Sabqponm
 abcryxxl
@@ -216,14 +605,264 @@ abdefghi";
 
     #[test]
     fn test_part1() {
-        assert_eq!(part1(&generator(SAMPLE)), 31);
-        assert_eq!(part1_dijkstra(&generator(SAMPLE)), 31);
-        assert_eq!(part1_astar(&generator(SAMPLE)), 31);
+        assert_eq!(part1(&generator(SAMPLE).unwrap()), 31);
+        assert_eq!(part1_dijkstra(&generator(SAMPLE).unwrap()), 31);
+        assert_eq!(part1_astar(&generator(SAMPLE).unwrap()), 31);
+    }
+
+    #[test]
+    fn test_route() {
+        let input = generator(SAMPLE).unwrap();
+        let route = route(&input).unwrap();
+        assert_eq!(route.len(), 32);
+        assert_eq!((route[0], route[31]), (Point::new(0, 0), Point::new(5, 2)));
+
+        let drawn = render(&input, &route);
+        assert_eq!(
+            drawn,
+            "v..v<<<<
+>v.vv<<^
+.v.v>E^^
+.>v>>>^^
+..>>>>>^"
+        );
+        assert_eq!(drawn.chars().filter(|c| "^v<>".contains(*c)).count(), 31);
+
+        let ansi = render_ansi(&input, &route);
+        let stripped = regex::Regex::new("\x1b\\[[0-9;]*m")
+            .unwrap()
+            .replace_all(&ansi, "");
+        assert_eq!(stripped, drawn);
+        assert!(ansi.starts_with("\x1b[48;5;232;38;5;255mv"));
+    }
+
+    #[test]
+    fn test_terrain() {
+        let input = generator(SAMPLE).unwrap();
+        assert_eq!(cheapest(&input, &Terrain::default()).unwrap().1, 31);
+
+        // the path cost has to add up from the elevation changes along it
+        let check = |terrain: &Terrain| {
+            let (path, cost) = cheapest(&input, terrain).unwrap();
+            let map = Map::new(&input);
+            let total: u32 = path
+                .iter()
+                .tuple_windows()
+                .map(|(a, b)| (terrain.cost)(map.grid[b.y][b.x] - map.grid[a.y][a.x]))
+                .sum();
+            assert_eq!(total, cost);
+            cost
+        };
+
+        let steep = Terrain {
+            cost: |delta| if delta > 0 { 3 } else { 1 },
+            ..Terrain::default()
+        };
+        assert_eq!(check(&steep), 81);
+
+        let diagonal = Terrain {
+            diagonals: true,
+            ..Terrain::default()
+        };
+        assert_eq!(check(&diagonal), 27);
+
+        // the sample spirals up without ever going down
+        let careful = Terrain {
+            max_descent: 0,
+            ..Terrain::default()
+        };
+        assert_eq!(check(&careful), 31);
+        let flat = Terrain {
+            max_climb: 0,
+            ..Terrain::default()
+        };
+        assert_eq!(cheapest(&input, &flat), None);
+    }
+
+    #[test]
+    fn test_heuristics() {
+        let heuristics = [
+            Heuristic::Zero,
+            Heuristic::Manhattan,
+            Heuristic::ElevationGap,
+        ];
+        let input = generator(SAMPLE).unwrap();
+        for heuristic in heuristics {
+            assert_eq!(search(&input, &Puzzle, heuristic).unwrap().cost, 31);
+        }
+
+        // the sample is too small to tell them apart, a bigger map isn't
+        let mut state = 12;
+        let input = loop {
+            let input = generator(&heightmap(&mut state, 60, 40)).unwrap();
+            if route(&input).is_some() {
+                break input;
+            }
+        };
+        let expanded =
+            heuristics.map(|heuristic| search(&input, &Puzzle, heuristic).unwrap().expanded);
+        assert!(
+            expanded[0] > expanded[1] && expanded[1] > expanded[2],
+            "{:?}",
+            expanded
+        );
+    }
+
+    /** xorshift, enough to scatter elevations without a dependency */
+    fn random(state: &mut u64) -> u64 {
+        *state ^= *state << 13;
+        *state ^= *state >> 7;
+        *state ^= *state << 17;
+        *state
+    }
+
+    /** rising from S in the top left to E in the bottom right, with some noise */
+    fn heightmap(state: &mut u64, cols: usize, rows: usize) -> String {
+        (0..rows)
+            .map(|y| {
+                (0..cols)
+                    .map(|x| match (x, y) {
+                        (0, 0) => 'S',
+                        _ if (x, y) == (cols - 1, rows - 1) => 'E',
+                        _ => {
+                            let slope = (x + y) * 25 / (cols + rows - 2);
+                            let noise = (random(state) % 3) as usize;
+                            (b'a' + (slope + noise).saturating_sub(1).min(25) as u8) as char
+                        }
+                    })
+                    .collect::<String>()
+            })
+            .join("\n")
+    }
+
+    #[test]
+    fn test_astar_matches_bfs() {
+        let steep = Terrain {
+            cost: |delta| if delta > 0 { 1 + delta as u32 } else { 1 },
+            ..Terrain::default()
+        };
+        let diagonal = Terrain {
+            max_climb: 2,
+            diagonals: true,
+            ..Terrain::default()
+        };
+
+        let mut state = 0x2022_1212;
+        let mut reachable = 0;
+        for _ in 0..100 {
+            let text = heightmap(&mut state, 20, 15);
+            let input = generator(&text).unwrap();
+            let shortest = route(&input).map(|path| path.len() as u32 - 1);
+            let expected =
+                [&steep, &diagonal].map(|rule| cheapest(&input, rule).map(|found| found.1));
+            reachable += shortest.is_some() as usize;
+
+            let cost = |found: Result<Search, usize>| found.ok().map(|found| found.cost);
+            for heuristic in [
+                Heuristic::Zero,
+                Heuristic::Manhattan,
+                Heuristic::ElevationGap,
+            ] {
+                assert_eq!(
+                    cost(search(&input, &Puzzle, heuristic)),
+                    shortest,
+                    "{}",
+                    text
+                );
+                for (rule, expected) in [&steep, &diagonal].into_iter().zip(expected) {
+                    assert_eq!(cost(search(&input, rule, heuristic)), expected, "{}", text);
+                }
+            }
+        }
+        // make sure both outcomes are exercised
+        assert!(reachable > 10 && reachable < 90, "{}", reachable);
+    }
+
+    #[test]
+    fn test_hike() {
+        let input = generator(SAMPLE).unwrap();
+        assert_eq!(at_elevation(&input, 'S'), vec![Point::new(0, 0)]);
+        assert_eq!(at_elevation(&input, 'a').len(), 6);
+        assert_eq!(elevation(&input, &Point::new(5, 2)), 'z');
+
+        // shortest hike from any b to any y
+        let ys = at_elevation(&input, 'y');
+        let found = hike(&input, &at_elevation(&input, 'b'), |coord| {
+            ys.contains(coord)
+        })
+        .unwrap();
+        assert_eq!(
+            (found.start, found.goal),
+            (Point::new(1, 4), Point::new(4, 1))
+        );
+        assert_eq!(found.steps(), 26);
+
+        // the top right corner, first reached climbing up the right edge
+        let corner = region(&input, &Point::new(6, 0), &Point::new(9, 1));
+        assert_eq!(corner.len(), 4);
+        let found = hike(&input, &at_elevation(&input, 'S'), |coord| {
+            corner.contains(coord)
+        })
+        .unwrap();
+        assert_eq!(found.goal, Point::new(7, 1));
+        assert_eq!(found.steps(), 14);
+
+        assert_eq!(hike(&input, &[], |_| true), None);
+        assert_eq!(hike(&input, &at_elevation(&input, 'a'), |_| false), None);
     }
 
     #[test]
     fn test_part2() {
-        assert_eq!(part2(&generator(SAMPLE)), 29);
-        assert_eq!(part2_dijkstra(&generator(SAMPLE)), 29);
+        assert_eq!(part2(&generator(SAMPLE).unwrap()), 29);
+        assert_eq!(part2_dijkstra(&generator(SAMPLE).unwrap()), 29);
+        assert_eq!(part2_field(&generator(SAMPLE).unwrap()), 29);
+        assert_eq!(part2_hike(&generator(SAMPLE).unwrap()), 29);
+    }
+
+    #[test]
+    fn test_distance_field() {
+        let field = DistanceField::new(&generator(SAMPLE).unwrap());
+        assert_eq!(field.get(&Point::new(0, 0)), Some(31));
+        assert_eq!(field.get(&Point::new(5, 2)), Some(0));
+        assert!(field.unreachable().is_empty());
+        assert_eq!(field.farthest_start(), Some((Point::new(0, 2), 31)));
+
+        // E is only reachable over y and z, cutting off both ends
+        let walled = DistanceField::new(&generator("Sayza\ncccEa").unwrap());
+        assert_eq!(walled.get(&Point::new(0, 0)), None);
+        assert_eq!(walled.get(&Point::new(2, 0)), Some(2));
+        assert_eq!(
+            walled.unreachable(),
+            vec![
+                vec![
+                    Point::new(0, 0),
+                    Point::new(1, 0),
+                    Point::new(0, 1),
+                    Point::new(1, 1),
+                    Point::new(2, 1)
+                ],
+                vec![Point::new(4, 0), Point::new(4, 1)],
+            ]
+        );
+        assert_eq!(walled.farthest_start(), None);
+
+        let mut ppm = Vec::new();
+        walled.write_ppm(&mut ppm).unwrap();
+        assert!(ppm.starts_with(b"P6\n5 2\n255\n"));
+        assert_eq!(ppm.len(), 11 + 5 * 2 * 3);
+        assert_eq!(&ppm[11..14], &[200, 0, 0]);
+    }
+
+    #[test]
+    fn test_generator() {
+        assert_eq!(
+            located(generator("SabE\nSbcd")),
+            "2:5 expected exactly one `S`, found 2"
+        );
+        assert_eq!(
+            located(generator("Sab\nEa")),
+            "2:1 rows must be the same width"
+        );
+        assert_eq!(located(generator("Sa1E")), "1:3 expected an elevation");
     }
 }
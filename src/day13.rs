@@ -1,94 +1,330 @@
-use std::{cmp::Ordering, ops::ControlFlow};
+use std::{cmp::Ordering, fmt};
 
 use itertools::{EitherOrBoth, Itertools};
-use serde_json::Value;
 
-type Input = Vec<(Vec<Value>, Vec<Value>)>;
+use crate::error::ParseError;
 
-pub fn generator(input: &str) -> Input {
-    input
-        .split("\n\n")
-        .map(|chunk| {
-            let pair = chunk.split_once('\n').unwrap();
-            (
-                serde_json::from_str(pair.0).unwrap(),
-                serde_json::from_str(pair.1).unwrap(),
-            )
-        })
-        .collect()
-}
+const DAY: u8 = 13;
 
-pub fn part1(input: &Input) -> usize {
-    let mut right_pairs: usize = 0;
+type Input = Vec<(Packet, Packet)>;
 
-    for (i, pair) in input.iter().enumerate() {
-        if recursive_parse(&pair.0, &pair.1) == ControlFlow::Break(true) {
-            right_pairs += i + 1;
+/** equality follows the ordering, so `[[2]]` equals `[2]` */
+#[derive(Debug, Clone)]
+pub enum Packet {
+    Int(u64),
+    List(Vec<Packet>),
+}
+impl Packet {
+    /** a whole line, which is always a list. `line` has to be a slice of `input` */
+    pub fn parse(input: &str, line: &str) -> Result<Self, ParseError> {
+        let mut parser = Parser {
+            input,
+            line,
+            position: 0,
+        };
+        if parser.peek() != Some(b'[') {
+            return Err(parser.error("expected `[`"));
+        }
+        let packet = parser.value()?;
+        match parser.peek() {
+            None => Ok(packet),
+            Some(_) => Err(parser.error("expected the end of the packet")),
+        }
+    }
+}
+impl Ord for Packet {
+    fn cmp(&self, other: &Self) -> Ordering {
+        match (self, other) {
+            (Packet::Int(left), Packet::Int(right)) => left.cmp(right),
+            // shorter lists that agree so far come first, which is how slices compare anyway
+            (Packet::List(left), Packet::List(right)) => left.cmp(right),
+            (Packet::Int(left), Packet::List(right)) => {
+                [Packet::Int(*left)].as_slice().cmp(right.as_slice())
+            }
+            (Packet::List(left), Packet::Int(right)) => {
+                left.as_slice().cmp([Packet::Int(*right)].as_slice())
+            }
         }
     }
+}
+impl PartialOrd for Packet {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+impl PartialEq for Packet {
+    fn eq(&self, other: &Self) -> bool {
+        self.cmp(other) == Ordering::Equal
+    }
+}
+impl Eq for Packet {}
+impl fmt::Display for Packet {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Packet::Int(value) => write!(f, "{}", value),
+            Packet::List(items) => write!(f, "[{}]", items.iter().join(",")),
+        }
+    }
+}
 
-    right_pairs
+/** walks the bytes of one line, numbers are read straight out of it */
+struct Parser<'a> {
+    input: &'a str,
+    line: &'a str,
+    position: usize,
 }
+impl Parser<'_> {
+    fn peek(&self) -> Option<u8> {
+        self.line.as_bytes().get(self.position).copied()
+    }
 
-pub fn part2(input: &Input) -> usize {
-    let mut flattened: Vec<Vec<Value>> = input.iter().fold(Vec::new(), |mut acc, pair| {
-        acc.push(pair.0.clone());
-        acc.push(pair.1.clone());
-        acc
-    });
-
-    let two: Vec<Value> = serde_json::from_str("[[2]]").unwrap();
-    let six: Vec<Value> = serde_json::from_str("[[6]]").unwrap();
-    flattened.push(two.clone());
-    flattened.push(six.clone());
-
-    flattened.sort_by(|left, right| match recursive_parse(left, right) {
-        ControlFlow::Break(true) => Ordering::Less,
-        ControlFlow::Break(false) => Ordering::Greater,
-        ControlFlow::Continue(_) => Ordering::Equal,
-    });
-
-    let mut iter = flattened.iter();
-    let two_pos = iter.position(|packet| packet == &two).unwrap() + 1;
-    let six_pos = iter.position(|packet| packet == &six).unwrap() + 1 + two_pos;
-
-    two_pos * six_pos
-}
-
-fn recursive_parse(left: &[Value], right: &[Value]) -> ControlFlow<bool> {
-    left.iter()
-        .zip_longest(right.iter())
-        .try_for_each(|pair| match pair {
-            EitherOrBoth::Left(_) => ControlFlow::Break(false),
-            EitherOrBoth::Right(_) => ControlFlow::Break(true),
-            EitherOrBoth::Both(l, r) => parse(l, r),
-        })
+    /** points at the current character, or just past the end of the line */
+    fn error(&self, message: &str) -> ParseError {
+        let rest = &self.line[self.position..];
+        let width = rest.chars().next().map_or(0, char::len_utf8);
+        ParseError::new(DAY, self.input, &rest[..width], message)
+    }
+
+    fn value(&mut self) -> Result<Packet, ParseError> {
+        match self.peek() {
+            Some(b'[') => self.list(),
+            Some(b'0'..=b'9') => self.int(),
+            _ => Err(self.error("expected `[` or a number")),
+        }
+    }
+
+    fn list(&mut self) -> Result<Packet, ParseError> {
+        self.position += 1;
+        let mut items = Vec::new();
+        if self.peek() == Some(b']') {
+            self.position += 1;
+            return Ok(Packet::List(items));
+        }
+
+        loop {
+            items.push(self.value()?);
+            match self.peek() {
+                Some(b',') => self.position += 1,
+                Some(b']') => {
+                    self.position += 1;
+                    return Ok(Packet::List(items));
+                }
+                _ => return Err(self.error("expected `,` or `]`")),
+            }
+        }
+    }
+
+    fn int(&mut self) -> Result<Packet, ParseError> {
+        let start = self.position;
+        while matches!(self.peek(), Some(b'0'..=b'9')) {
+            self.position += 1;
+        }
+        let digits = &self.line[start..self.position];
+        digits
+            .parse()
+            .map(Packet::Int)
+            .map_err(|_| ParseError::new(DAY, self.input, digits, "number too large"))
+    }
 }
 
-fn parse(left: &Value, right: &Value) -> ControlFlow<bool> {
-    if left.is_number() && right.is_number() {
-        if left.as_u64() < right.as_u64() {
-            return ControlFlow::Break(true);
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Side {
+    Left,
+    Right,
+}
+impl fmt::Display for Side {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Side::Left => write!(f, "left"),
+            Side::Right => write!(f, "right"),
         }
-        if left.as_u64() > right.as_u64() {
-            return ControlFlow::Break(false);
+    }
+}
+
+#[derive(Debug, Clone)]
+pub enum Step {
+    Compare(Packet, Packet),
+    /** mixed types, the integer on this side became the list */
+    Convert(Side, Packet),
+    Smaller(Side),
+    RanOut(Side),
+}
+
+/** one line of the puzzle's walkthrough, `depth` 0 being the whole pair */
+#[derive(Debug, Clone)]
+pub struct Event {
+    pub depth: usize,
+    pub step: Step,
+}
+impl Event {
+    fn new(depth: usize, step: Step) -> Self {
+        Self { depth, step }
+    }
+}
+impl fmt::Display for Event {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let verdict = |side: &Side| match side {
+            Side::Left => "so inputs are in the right order",
+            Side::Right => "so inputs are not in the right order",
+        };
+        let side = |side: &Side| match side {
+            Side::Left => "Left",
+            Side::Right => "Right",
+        };
+
+        write!(f, "{}- ", "  ".repeat(self.depth))?;
+        match &self.step {
+            Step::Compare(left, right) => write!(f, "Compare {} vs {}", left, right),
+            Step::Convert(side, packet) => write!(
+                f,
+                "Mixed types; convert {} to {} and retry comparison",
+                side, packet
+            ),
+            Step::Smaller(which) => {
+                write!(f, "{} side is smaller, {}", side(which), verdict(which))
+            }
+            Step::RanOut(which) => write!(
+                f,
+                "{} side ran out of items, {}",
+                side(which),
+                verdict(which)
+            ),
         }
     }
-    if left.is_array() && right.is_number() {
-        return recursive_parse(left.as_array().unwrap(), &[right.clone()]);
+}
+
+/** how a pair was judged, the same as `Ord` but showing its work */
+#[derive(Debug, Clone)]
+pub struct Explanation {
+    #[allow(dead_code)]
+    pub order: Ordering,
+    pub events: Vec<Event>,
+}
+impl Explanation {
+    pub fn new(left: &Packet, right: &Packet) -> Self {
+        let mut events = Vec::new();
+        let order = explain(left, right, 0, &mut events);
+        Self { order, events }
     }
-    if left.is_number() && right.is_array() {
-        return recursive_parse(&[left.clone()], right.as_array().unwrap());
+}
+impl fmt::Display for Explanation {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        self.events
+            .iter()
+            .try_for_each(|event| writeln!(f, "{}", event))
     }
-    if left.is_array() && right.is_array() {
-        return recursive_parse(left.as_array().unwrap(), right.as_array().unwrap());
+}
+
+fn explain(left: &Packet, right: &Packet, depth: usize, events: &mut Vec<Event>) -> Ordering {
+    events.push(Event::new(
+        depth,
+        Step::Compare(left.clone(), right.clone()),
+    ));
+
+    match (left, right) {
+        (Packet::Int(l), Packet::Int(r)) => {
+            let order = l.cmp(r);
+            match order {
+                Ordering::Less => events.push(Event::new(depth + 1, Step::Smaller(Side::Left))),
+                Ordering::Greater => events.push(Event::new(depth + 1, Step::Smaller(Side::Right))),
+                Ordering::Equal => {}
+            }
+            order
+        }
+        (Packet::List(l), Packet::List(r)) => {
+            for pair in l.iter().zip_longest(r) {
+                match pair {
+                    EitherOrBoth::Both(l, r) => match explain(l, r, depth + 1, events) {
+                        Ordering::Equal => {}
+                        order => return order,
+                    },
+                    EitherOrBoth::Left(_) => {
+                        events.push(Event::new(depth + 1, Step::RanOut(Side::Right)));
+                        return Ordering::Greater;
+                    }
+                    EitherOrBoth::Right(_) => {
+                        events.push(Event::new(depth + 1, Step::RanOut(Side::Left)));
+                        return Ordering::Less;
+                    }
+                }
+            }
+            Ordering::Equal
+        }
+        (Packet::Int(l), Packet::List(_)) => {
+            let converted = Packet::List(vec![Packet::Int(*l)]);
+            events.push(Event::new(
+                depth + 1,
+                Step::Convert(Side::Left, converted.clone()),
+            ));
+            explain(&converted, right, depth + 1, events)
+        }
+        (Packet::List(_), Packet::Int(r)) => {
+            let converted = Packet::List(vec![Packet::Int(*r)]);
+            events.push(Event::new(
+                depth + 1,
+                Step::Convert(Side::Right, converted.clone()),
+            ));
+            explain(left, &converted, depth + 1, events)
+        }
     }
-    ControlFlow::Continue(())
+}
+
+/** every pair walked through like the puzzle text */
+#[allow(dead_code)]
+pub fn report(input: &Input) -> String {
+    input
+        .iter()
+        .enumerate()
+        .map(|(i, (left, right))| {
+            format!("== Pair {} ==\n{}", i + 1, Explanation::new(left, right))
+        })
+        .join("\n")
+}
+
+pub fn generator(input: &str) -> Result<Input, ParseError> {
+    input
+        .split("\n\n")
+        .map(|chunk| {
+            let pair = chunk
+                .split_once('\n')
+                .ok_or_else(|| ParseError::new(DAY, input, chunk, "expected a pair of packets"))?;
+            // a saved puzzle file keeps its trailing newline, and CRLF lines keep a `\r`
+            Ok((
+                Packet::parse(input, pair.0.trim_end())?,
+                Packet::parse(input, pair.1.trim_end())?,
+            ))
+        })
+        .collect()
+}
+
+pub fn part1(input: &Input) -> usize {
+    input
+        .iter()
+        .enumerate()
+        .filter(|(_, (left, right))| left < right)
+        .map(|(i, _)| i + 1)
+        .sum()
+}
+
+pub fn part2(input: &Input) -> usize {
+    let divider = |value| Packet::List(vec![Packet::List(vec![Packet::Int(value)])]);
+    let (two, six) = (divider(2), divider(6));
+
+    let packets = input
+        .iter()
+        .flat_map(|(left, right)| [left, right])
+        .collect_vec();
+
+    // count what sorts ahead instead of searching, `==` can't tell `[2]` from `[[2]]`
+    let before = |divider: &Packet| packets.iter().filter(|packet| **packet < divider).count();
+    // the two divider sorts ahead of the six one
+    (before(&two) + 1) * (before(&six) + 2)
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::error::located;
 
     const SAMPLE: &str = "[1,1,3,1,1]
 [1,1,5,1,1]
@@ -114,13 +350,109 @@ mod tests {
 [1,[2,[3,[4,[5,6,7]]]],8,9]
 [1,[2,[3,[4,[5,6,0]]]],8,9]";
 
+    #[test]
+    fn test_packet() {
+        for line in SAMPLE.lines().filter(|line| !line.is_empty()) {
+            assert_eq!(Packet::parse(line, line).unwrap().to_string(), line);
+        }
+
+        let packet = |line: &str| Packet::parse(line, line).unwrap();
+        assert!(packet("[[1],[2,3,4]]") < packet("[[1],4]"));
+        assert!(packet("[7,7,7,7]") > packet("[7,7,7]"));
+        assert_eq!(packet("[[2]]"), packet("[2]"));
+        assert_eq!(packet("[10,[]]").to_string(), "[10,[]]");
+
+        let error = |line: &str| located(Packet::parse(line, line));
+        assert_eq!(error("[1,,2]"), "1:4 expected `[` or a number");
+        assert_eq!(error("[1,[2]"), "1:7 expected `,` or `]`");
+        assert_eq!(error("[1]]"), "1:4 expected the end of the packet");
+        assert_eq!(error("3"), "1:1 expected `[`");
+        assert_eq!(error("[99999999999999999999]"), "1:2 number too large");
+    }
+
+    #[test]
+    fn test_explain() {
+        let input = generator(SAMPLE).unwrap();
+        for (left, right) in &input {
+            assert_eq!(Explanation::new(left, right).order, left.cmp(right));
+        }
+
+        assert!(report(&input).starts_with(
+            "== Pair 1 ==
+- Compare [1,1,3,1,1] vs [1,1,5,1,1]
+  - Compare 1 vs 1
+  - Compare 1 vs 1
+  - Compare 3 vs 5
+    - Left side is smaller, so inputs are in the right order
+
+== Pair 2 ==
+- Compare [[1],[2,3,4]] vs [[1],4]
+  - Compare [1] vs [1]
+    - Compare 1 vs 1
+  - Compare [2,3,4] vs 4
+    - Mixed types; convert right to [4] and retry comparison
+    - Compare [2,3,4] vs [4]
+      - Compare 2 vs 4
+        - Left side is smaller, so inputs are in the right order
+
+== Pair 3 ==
+- Compare [9] vs [[8,7,6]]
+  - Compare 9 vs [8,7,6]
+    - Mixed types; convert left to [9] and retry comparison
+    - Compare [9] vs [8,7,6]
+      - Compare 9 vs 8
+        - Right side is smaller, so inputs are not in the right order
+
+== Pair 4 ==
+- Compare [[4,4],4,4] vs [[4,4],4,4,4]
+  - Compare [4,4] vs [4,4]
+    - Compare 4 vs 4
+    - Compare 4 vs 4
+  - Compare 4 vs 4
+  - Compare 4 vs 4
+  - Left side ran out of items, so inputs are in the right order
+
+== Pair 5 ==
+- Compare [7,7,7,7] vs [7,7,7]
+  - Compare 7 vs 7
+  - Compare 7 vs 7
+  - Compare 7 vs 7
+  - Right side ran out of items, so inputs are not in the right order
+"
+        ));
+
+        let explanation = Explanation::new(&input[5].0, &input[5].1);
+        assert_eq!(explanation.events.len(), 2);
+        assert!(matches!(
+            explanation.events[1],
+            Event {
+                depth: 1,
+                step: Step::RanOut(Side::Left)
+            }
+        ));
+    }
+
     #[test]
     fn test_part1() {
-        assert_eq!(part1(&generator(SAMPLE)), 13);
+        assert_eq!(part1(&generator(SAMPLE).unwrap()), 13);
     }
 
     #[test]
     fn test_part2() {
-        assert_eq!(part2(&generator(SAMPLE)), 140);
+        assert_eq!(part2(&generator(SAMPLE).unwrap()), 140);
+        // both tie with `[[2]]` without being it
+        assert_eq!(part2(&generator("[2]\n[[[2]]]").unwrap()), 4);
+    }
+
+    #[test]
+    fn test_generator() {
+        assert_eq!(
+            located(generator("[1]\n[2]\n\n[3]")),
+            "4:1 expected a pair of packets"
+        );
+
+        let saved = format!("{}\n", SAMPLE);
+        assert_eq!(part1(&generator(&saved).unwrap()), 13);
+        assert_eq!(generator("[1]\r\n[2]\r\n").unwrap().len(), 1);
     }
 }
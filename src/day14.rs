@@ -2,11 +2,11 @@ use grid::*;
 use pathfinding::num_traits::ToPrimitive;
 use std::ops::ControlFlow;
 
-#[derive(Debug, Clone, Copy, PartialEq, Eq)]
-pub struct Coord {
-    x: usize,
-    y: usize,
-}
+use crate::{error::ParseError, geom::Point};
+
+const DAY: u8 = 14;
+
+type Coord = Point<usize>;
 pub struct Input {
     lines: Lines,
     x: usize,
@@ -16,17 +16,23 @@ type Lines = Vec<Vec<Coord>>;
 
 const SOURCE: Coord = Coord { x: 500, y: 0 };
 
-pub fn generator(input: &str) -> Input {
+pub fn generator(input: &str) -> Result<Input, ParseError> {
     let mut max_y: usize = SOURCE.y;
     let mut max_x: usize = SOURCE.x;
+    let number = |text: &str| {
+        text.parse::<usize>()
+            .map_err(|_| ParseError::new(DAY, input, text, "expected a number"))
+    };
     let lines = input
         .lines()
         .map(|line| {
             line.split(" -> ")
                 .map(|each| {
-                    let coord = each.split_once(',').unwrap();
-                    let x: usize = coord.0.parse().unwrap();
-                    let y: usize = coord.1.parse().unwrap();
+                    let coord = each
+                        .split_once(',')
+                        .ok_or_else(|| ParseError::new(DAY, input, each, "expected `x,y`"))?;
+                    let x: usize = number(coord.0)?;
+                    let y: usize = number(coord.1)?;
                     if x > max_x {
                         max_x = x;
                     }
@@ -34,17 +40,31 @@ pub fn generator(input: &str) -> Input {
                         max_y = y;
                     }
 
-                    Coord { x, y }
+                    Ok(Coord { x, y })
+                })
+                .collect::<Result<Vec<_>, _>>()
+                .and_then(|points| {
+                    match points
+                        .windows(2)
+                        .find(|line| line[0].x != line[1].x && line[0].y != line[1].y)
+                    {
+                        Some(_) => Err(ParseError::new(
+                            DAY,
+                            input,
+                            line,
+                            "rock paths must be straight",
+                        )),
+                        None => Ok(points),
+                    }
                 })
-                .collect()
         })
-        .collect();
+        .collect::<Result<_, _>>()?;
 
-    Input {
+    Ok(Input {
         lines,
         x: max_x,
         y: max_y,
-    }
+    })
 }
 
 pub fn part1(input: &Input) -> usize {
@@ -152,17 +172,31 @@ fn draw_floor(grid: &mut Grid<char>) {
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::error::located;
 
     const SAMPLE: &str = "498,4 -> 498,6 -> 496,6
 503,4 -> 502,4 -> 502,9 -> 494,9";
 
     #[test]
     fn test_part1() {
-        assert_eq!(part1(&generator(SAMPLE)), 24);
+        assert_eq!(part1(&generator(SAMPLE).unwrap()), 24);
     }
 
     #[test]
     fn test_part2() {
-        assert_eq!(part2(&generator(SAMPLE)), 93);
+        assert_eq!(part2(&generator(SAMPLE).unwrap()), 93);
+    }
+
+    #[test]
+    fn test_generator() {
+        assert_eq!(
+            located(generator("498,4 -> 498,6 -> 496,6\n503,4 -> 502,5")),
+            "2:1 rock paths must be straight"
+        );
+        assert_eq!(located(generator("498;4 -> 498,6")), "1:1 expected `x,y`");
+        assert_eq!(
+            located(generator("498,y -> 498,6")),
+            "1:5 expected a number"
+        );
     }
 }
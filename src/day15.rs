@@ -1,15 +1,14 @@
+use std::{io, ops::RangeInclusive};
+
+use itertools::Itertools;
+
+use crate::{error::ParseError, geom::Point};
+
+const DAY: u8 = 15;
+
 type Input = Vec<Pair>;
 
-#[derive(Debug, Clone, Copy, PartialEq, Eq)]
-pub struct Coord {
-    x: i32,
-    y: i32,
-}
-impl Coord {
-    fn within(&self, max: &i32) -> bool {
-        self.x >= 0 && self.x <= *max && self.y >= 0 && self.y <= *max
-    }
-}
+type Coord = Point<i32>;
 
 #[derive(Debug)]
 pub struct Pair {
@@ -18,77 +17,235 @@ pub struct Pair {
     distance: i32,
 }
 
-pub fn generator(input: &str) -> Input {
+pub fn generator(input: &str) -> Result<Input, ParseError> {
     input
         .lines()
         .map(|line| {
-            let pair = line[10..].split_once(": closest beacon is at ").unwrap();
-            let sensor = pair.0[2..].split_once(", y=").unwrap();
-            let beacon = pair.1[2..].split_once(", y=").unwrap();
+            let pair = line
+                .strip_prefix("Sensor at ")
+                .and_then(|rest| rest.split_once(": closest beacon is at "))
+                .ok_or_else(|| ParseError::new(DAY, input, line, "expected a sensor report"))?;
             let mut pair = Pair {
-                sensor: Coord {
-                    x: sensor.0.parse().unwrap(),
-                    y: sensor.1.parse().unwrap(),
-                },
-                beacon: Coord {
-                    x: beacon.0.parse().unwrap(),
-                    y: beacon.1.parse().unwrap(),
-                },
+                sensor: coord(input, pair.0)?,
+                beacon: coord(input, pair.1)?,
                 distance: 0,
             };
-            pair.distance = manhattan_distance(&pair.beacon, &pair.sensor);
-            pair
+            pair.distance = pair.beacon.manhattan_distance(&pair.sensor);
+            Ok(pair)
         })
         .collect()
 }
+
+fn coord(input: &str, text: &str) -> Result<Coord, ParseError> {
+    let (x, y) = text
+        .strip_prefix("x=")
+        .and_then(|rest| rest.split_once(", y="))
+        .ok_or_else(|| ParseError::new(DAY, input, text, "expected `x=X, y=Y`"))?;
+    let number = |text: &str| {
+        text.parse()
+            .map_err(|_| ParseError::new(DAY, input, text, "expected a number"))
+    };
+    Ok(Coord {
+        x: number(x)?,
+        y: number(y)?,
+    })
+}
+
+/** which row to count, where to search for the distress beacon and how to tune it */
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SensorQuery {
+    pub row: i32,
+    pub min: Point<i32>,
+    pub max: Point<i32>,
+    pub multiplier: i64,
+}
+impl Default for SensorQuery {
+    fn default() -> Self {
+        Self {
+            row: 2_000_000,
+            min: Point::new(0, 0),
+            max: Point::new(4_000_000, 4_000_000),
+            multiplier: 4_000_000,
+        }
+    }
+}
+impl SensorQuery {
+    /** the smaller scenario from the puzzle text */
+    #[allow(dead_code)]
+    pub fn sample() -> Self {
+        Self {
+            row: 10,
+            max: Point::new(20, 20),
+            ..Default::default()
+        }
+    }
+
+    pub fn tuning_frequency(&self, coord: &Point<i32>) -> i64 {
+        (coord.x as i64) * self.multiplier + (coord.y as i64)
+    }
+}
+
 pub fn part1(input: &Input) -> usize {
-    part1_row(input, None)
+    part1_row(input, &SensorQuery::default())
+}
+
+/** cells on the query row where a beacon can't be */
+pub fn part1_row(input: &Input, query: &SensorQuery) -> usize {
+    let y = query.row;
+    let covered = row_coverage(input, y);
+
+    // beacons sit inside the coverage but can't be where a beacon isn't
+    let beacons = input
+        .iter()
+        .filter(|pair| pair.beacon.y == y)
+        .map(|pair| pair.beacon.x)
+        .unique()
+        .filter(|x| covered.iter().any(|range| range.contains(x)))
+        .count();
+
+    covered
+        .iter()
+        .map(|range| (range.end() - range.start()) as usize + 1)
+        .sum::<usize>()
+        - beacons
+}
+
+/** x ranges covered by any sensor on row `y`, sorted and merged */
+pub fn row_coverage(input: &Input, y: i32) -> Vec<RangeInclusive<i32>> {
+    let mut merged: Vec<RangeInclusive<i32>> = Vec::new();
+
+    // each diamond crosses the row as a single span, narrowing with distance from the sensor
+    let spans = input
+        .iter()
+        .filter_map(|pair| {
+            let reach = pair.distance - pair.sensor.y.abs_diff(y) as i32;
+            (reach >= 0).then(|| pair.sensor.x - reach..=pair.sensor.x + reach)
+        })
+        .sorted_by_key(|span| *span.start());
+
+    for span in spans {
+        match merged.last_mut() {
+            // touching spans merge too, there's no gap between x and x + 1
+            Some(last) if *span.start() <= last.end() + 1 => {
+                if span.end() > last.end() {
+                    *last = *last.start()..=*span.end();
+                }
+            }
+            _ => merged.push(span),
+        }
+    }
+
+    merged
 }
 
-fn part1_row(input: &Input, row: Option<i32>) -> usize {
-    let y = row.unwrap_or(2_000_000);
+/** every cell in the query box that no sensor can see, row by row */
+#[allow(dead_code)]
+pub fn uncovered(input: &Input, query: &SensorQuery) -> Vec<Coord> {
+    let mut cells: Vec<Coord> = Vec::new();
 
-    let mut count = 0;
-    for x in -6_000_000..6_000_000 {
-        if check_coverage(input, y, x) {
-            count += 1;
+    for y in query.min.y..=query.max.y {
+        let mut x = query.min.x;
+        for range in row_coverage(input, y) {
+            if *range.start() > query.max.x {
+                break;
+            }
+            cells.extend((x..*range.start()).map(|x| Point::new(x, y)));
+            x = x.max(range.end() + 1);
         }
+        cells.extend((x..=query.max.x).map(|x| Point::new(x, y)));
     }
 
-    count
+    cells
 }
 
 pub fn part2(input: &Input) -> i64 {
-    part2_max(input, None)
+    part2_max(input, &SensorQuery::default())
 }
 
-fn part2_max(input: &Input, max: Option<i32>) -> i64 {
-    let max = max.unwrap_or(4_000_000);
-
-    for edge in input.iter().flat_map(|pair| perimeter(pair, &max)) {
+pub fn part2_max(input: &Input, query: &SensorQuery) -> i64 {
+    for edge in input.iter().flat_map(|pair| perimeter(pair, query)) {
         if !check_coverage(input, edge.y, edge.x) && !input.iter().any(|pair| pair.beacon == edge) {
-            return (edge.x as i64) * 4_000_000 + (edge.y as i64);
+            return query.tuning_frequency(&edge);
         }
     }
 
     panic!("no value found")
 }
 
-fn manhattan_distance(a: &Coord, b: &Coord) -> i32 {
-    (a.x.abs_diff(b.x) + a.y.abs_diff(b.y)).try_into().unwrap()
+pub fn part2_lines(input: &Input) -> i64 {
+    gap_by_lines(input, &SensorQuery::default())
+        .unwrap_or_else(|| panic!("no value found"))
+        .1
+}
+
+/** intersect the diagonals just outside each sensor's diamond, falling back to a row sweep for a gap wedged between parallel edges that never cross */
+pub fn gap_by_lines(input: &Input, query: &SensorQuery) -> Option<(Coord, i64)> {
+    let (min, max) = (query.min, query.max);
+
+    // y = x + rising, y = -x + falling
+    let rising = input
+        .iter()
+        .flat_map(|pair| {
+            let offset = pair.sensor.y - pair.sensor.x;
+            [offset - pair.distance - 1, offset + pair.distance + 1]
+        })
+        .unique()
+        .collect_vec();
+    let falling = input
+        .iter()
+        .flat_map(|pair| {
+            let offset = pair.sensor.y + pair.sensor.x;
+            [offset - pair.distance - 1, offset + pair.distance + 1]
+        })
+        .unique()
+        .collect_vec();
+
+    let intersections = rising
+        .iter()
+        .cartesian_product(falling.iter())
+        .filter(|(a, b)| (*b - *a) % 2 == 0)
+        .map(|(a, b)| Point::new((b - a) / 2, (a + b) / 2));
+    // a gap tucked into a corner of the box only needs one sensor edge, so check those too
+    let corners = [min, Point::new(max.x, min.y), Point::new(min.x, max.y), max];
+
+    intersections
+        .chain(corners)
+        .filter(|coord| coord.within(&min, &max))
+        .find(|coord| {
+            input
+                .iter()
+                .all(|pair| pair.sensor.manhattan_distance(coord) > pair.distance)
+        })
+        .or_else(|| first_uncovered(input, query))
+        .map(|coord| (coord, query.tuning_frequency(&coord)))
+}
+
+/** like `uncovered` but stops at the first gap */
+fn first_uncovered(input: &Input, query: &SensorQuery) -> Option<Coord> {
+    (query.min.y..=query.max.y).find_map(|y| {
+        let mut x = query.min.x;
+        for range in row_coverage(input, y) {
+            if *range.start() > x {
+                break;
+            }
+            x = x.max(range.end() + 1);
+        }
+        (x <= query.max.x).then(|| Point::new(x, y))
+    })
 }
 
 fn check_coverage(input: &Input, y: i32, x: i32) -> bool {
     let coord = Coord { x, y };
     input
         .iter()
-        .any(|pair| manhattan_distance(&pair.sensor, &coord) <= pair.distance)
+        .any(|pair| pair.sensor.manhattan_distance(&coord) <= pair.distance)
         && input.iter().all(|pair| pair.beacon != coord)
 }
 
 /** coords just past sensor edge */
-fn perimeter(pair: &Pair, max: &i32) -> Vec<Coord> {
+fn perimeter(pair: &Pair, query: &SensorQuery) -> Vec<Coord> {
     let mut edges: Vec<Coord> = Vec::new();
+    let (min, max) = (query.min, query.max);
 
     let up = Coord {
         x: pair.sensor.x,
@@ -124,16 +281,16 @@ fn perimeter(pair: &Pair, max: &i32) -> Vec<Coord> {
             x: left.x + i,
             y: up.y - i,
         };
-        if ne.within(max) {
+        if ne.within(&min, &max) {
             edges.push(ne);
         }
-        if se.within(max) {
+        if se.within(&min, &max) {
             edges.push(se);
         }
-        if sw.within(max) {
+        if sw.within(&min, &max) {
             edges.push(sw);
         }
-        if nw.within(max) {
+        if nw.within(&min, &max) {
             edges.push(nw);
         }
     }
@@ -141,9 +298,183 @@ fn perimeter(pair: &Pair, max: &i32) -> Vec<Coord> {
     edges
 }
 
+/** world rectangle to draw, downscaled so it fits within `columns` x `rows` cells */
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Viewport {
+    pub min: Point<i32>,
+    pub max: Point<i32>,
+    pub columns: usize,
+    pub rows: usize,
+}
+impl Viewport {
+    /** everything any sensor can see */
+    #[allow(dead_code)]
+    pub fn fit(input: &Input, columns: usize, rows: usize) -> Self {
+        let min = Point::new(
+            input
+                .iter()
+                .map(|p| p.sensor.x - p.distance)
+                .min()
+                .unwrap_or(0),
+            input
+                .iter()
+                .map(|p| p.sensor.y - p.distance)
+                .min()
+                .unwrap_or(0),
+        );
+        let max = Point::new(
+            input
+                .iter()
+                .map(|p| p.sensor.x + p.distance)
+                .max()
+                .unwrap_or(0),
+            input
+                .iter()
+                .map(|p| p.sensor.y + p.distance)
+                .max()
+                .unwrap_or(0),
+        );
+        Self {
+            min,
+            max,
+            columns,
+            rows,
+        }
+    }
+
+    /** world units per cell, the same on both axes so diamonds keep their shape */
+    fn scale(&self) -> i64 {
+        let width = (self.max.x as i64 - self.min.x as i64 + 1).max(1);
+        let height = (self.max.y as i64 - self.min.y as i64 + 1).max(1);
+        let fit =
+            |length: i64, cells: usize| (length + cells.max(1) as i64 - 1) / cells.max(1) as i64;
+        fit(width, self.columns).max(fit(height, self.rows)).max(1)
+    }
+
+    fn size(&self) -> (usize, usize) {
+        let scale = self.scale();
+        let cells = |length: i64| ((length.max(1) + scale - 1) / scale) as usize;
+        (
+            cells(self.max.x as i64 - self.min.x as i64 + 1),
+            cells(self.max.y as i64 - self.min.y as i64 + 1),
+        )
+    }
+
+    fn cell(&self, coord: &Coord) -> Option<(usize, usize)> {
+        if !coord.within(&self.min, &self.max) {
+            return None;
+        }
+        let scale = self.scale();
+        Some((
+            ((coord.x as i64 - self.min.x as i64) / scale) as usize,
+            ((coord.y as i64 - self.min.y as i64) / scale) as usize,
+        ))
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Cell {
+    Empty,
+    Covered,
+    Sensor,
+    Beacon,
+    Gap,
+}
+impl Cell {
+    fn symbol(&self) -> char {
+        match self {
+            Cell::Empty => '.',
+            Cell::Covered => '#',
+            Cell::Sensor => 'S',
+            Cell::Beacon => 'B',
+            Cell::Gap => 'X',
+        }
+    }
+
+    fn color(&self) -> [u8; 3] {
+        match self {
+            Cell::Empty => [20, 20, 30],
+            Cell::Covered => [70, 110, 160],
+            Cell::Sensor => [240, 200, 40],
+            Cell::Beacon => [220, 60, 60],
+            Cell::Gap => [60, 230, 90],
+        }
+    }
+}
+
+/** a downscaled cell shows coverage at its center, markers win wherever they land */
+fn rasterize(input: &Input, view: &Viewport, gap: Option<Coord>) -> Vec<Vec<Cell>> {
+    let scale = view.scale();
+    let (columns, rows) = view.size();
+    let center = |min: i32, index: usize| (min as i64 + index as i64 * scale + scale / 2) as i32;
+
+    let mut cells: Vec<Vec<Cell>> = (0..rows)
+        .map(|row| {
+            let covered = row_coverage(input, center(view.min.y, row));
+            (0..columns)
+                .map(|column| {
+                    let x = center(view.min.x, column);
+                    match covered.iter().any(|range| range.contains(&x)) {
+                        true => Cell::Covered,
+                        false => Cell::Empty,
+                    }
+                })
+                .collect()
+        })
+        .collect();
+
+    let mut mark = |coord: &Coord, cell: Cell| {
+        if let Some((x, y)) = view.cell(coord) {
+            cells[y][x] = cell;
+        }
+    };
+    for pair in input {
+        mark(&pair.beacon, Cell::Beacon);
+    }
+    for pair in input {
+        mark(&pair.sensor, Cell::Sensor);
+    }
+    if let Some(gap) = gap {
+        mark(&gap, Cell::Gap);
+    }
+
+    cells
+}
+
+/** S sensors, B beacons, # coverage, X the gap */
+#[allow(dead_code)]
+pub fn render(input: &Input, view: &Viewport, gap: Option<Coord>) -> String {
+    rasterize(input, view, gap)
+        .iter()
+        .map(|row| row.iter().map(Cell::symbol).collect::<String>())
+        .collect_vec()
+        .join("\n")
+}
+
+/** same picture as `render`, as a binary PPM image */
+#[allow(dead_code)]
+pub fn write_ppm(
+    input: &Input,
+    view: &Viewport,
+    gap: Option<Coord>,
+    out: &mut impl io::Write,
+) -> io::Result<()> {
+    let cells = rasterize(input, view, gap);
+    let (columns, rows) = view.size();
+
+    write!(out, "P6\n{} {}\n255\n", columns, rows)?;
+    for row in cells {
+        for cell in row {
+            out.write_all(&cell.color())?;
+        }
+    }
+    out.flush()
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::error::located;
 
     const SAMPLE: &str = "Sensor at x=2, y=18: closest beacon is at x=-2, y=15
 Sensor at x=9, y=16: closest beacon is at x=10, y=16
@@ -162,11 +493,137 @@ Sensor at x=20, y=1: closest beacon is at x=15, y=3";
 
     #[test]
     fn test_part1() {
-        assert_eq!(part1_row(&generator(SAMPLE), Some(10)), 26);
+        assert_eq!(
+            part1_row(&generator(SAMPLE).unwrap(), &SensorQuery::sample()),
+            26
+        );
+    }
+
+    #[test]
+    fn test_row_coverage() {
+        let input = generator(SAMPLE).unwrap();
+        assert_eq!(row_coverage(&input, 10), vec![-2..=24]);
+        assert_eq!(row_coverage(&input, 11), vec![-3..=13, 15..=25]);
+        assert_eq!(row_coverage(&input, -100), vec![]);
     }
 
     #[test]
     fn test_part2() {
-        assert_eq!(part2_max(&generator(SAMPLE), Some(20)), 56000011);
+        let query = SensorQuery::sample();
+        assert_eq!(part2_max(&generator(SAMPLE).unwrap(), &query), 56000011);
+        assert_eq!(
+            gap_by_lines(&generator(SAMPLE).unwrap(), &query),
+            Some((Point::new(14, 11), 56000011))
+        );
+    }
+
+    #[test]
+    fn test_gap_by_lines() {
+        let query = SensorQuery {
+            max: Point::new(4, 4),
+            ..SensorQuery::sample()
+        };
+        // one diamond reaching every cell but (4, 0), which no pair of edges crosses inside the box
+        let corner = generator("Sensor at x=0, y=4: closest beacon is at x=0, y=-3").unwrap();
+        assert_eq!(
+            gap_by_lines(&corner, &query),
+            Some((Point::new(4, 0), 16_000_000))
+        );
+
+        // (4, 4) sits where two falling edges run together, no rising edge passes through it
+        let parallel = generator(
+            "Sensor at x=6, y=6: closest beacon is at x=6, y=9
+Sensor at x=-2, y=5: closest beacon is at x=-2, y=10
+Sensor at x=2, y=1: closest beacon is at x=2, y=-3
+Sensor at x=7, y=1: closest beacon is at x=7, y=-3",
+        )
+        .unwrap();
+        let wider = SensorQuery {
+            max: Point::new(6, 6),
+            ..query
+        };
+        assert_eq!(uncovered(&parallel, &wider), vec![Point::new(4, 4)]);
+        assert_eq!(
+            gap_by_lines(&parallel, &wider),
+            Some((Point::new(4, 4), 16_000_004))
+        );
+        assert_eq!(part2_max(&parallel, &wider), 16_000_004);
+
+        let covered = generator("Sensor at x=0, y=4: closest beacon is at x=4, y=0").unwrap();
+        assert_eq!(gap_by_lines(&covered, &query), None);
+
+        let plugged = format!(
+            "{}\nSensor at x=14, y=11: closest beacon is at x=14, y=12",
+            SAMPLE
+        );
+        assert_eq!(
+            gap_by_lines(&generator(&plugged).unwrap(), &SensorQuery::sample()),
+            None
+        );
+    }
+
+    #[test]
+    fn test_uncovered() {
+        let input = generator(SAMPLE).unwrap();
+        assert_eq!(
+            uncovered(&input, &SensorQuery::sample()),
+            vec![Point::new(14, 11)]
+        );
+
+        // widen the box past the sensors and every corner opens up
+        let query = SensorQuery {
+            min: Point::new(-10, -10),
+            max: Point::new(-9, -9),
+            ..SensorQuery::sample()
+        };
+        assert_eq!(uncovered(&input, &query).len(), 4);
+    }
+
+    #[test]
+    fn test_render() {
+        let input = generator(SAMPLE).unwrap();
+        let query = SensorQuery::sample();
+        let gap = gap_by_lines(&input, &query).map(|(coord, _)| coord);
+        let view = Viewport {
+            min: query.min,
+            max: query.max,
+            columns: 21,
+            rows: 21,
+        };
+
+        let picture = render(&input, &view, gap);
+        let rows = picture.lines().collect_vec();
+        assert_eq!(rows.len(), 21);
+        assert_eq!(rows[10], "##B##################");
+        assert_eq!(rows[11], "S#############X######");
+
+        // squeezed to a third, and still one cell per row/col
+        let small = Viewport {
+            columns: 7,
+            rows: 7,
+            ..view
+        };
+        assert_eq!(render(&input, &small, gap).lines().count(), 7);
+
+        let mut image: Vec<u8> = Vec::new();
+        write_ppm(&input, &small, gap, &mut image).unwrap();
+        assert!(image.starts_with(b"P6\n7 7\n255\n"));
+        assert_eq!(image.len(), b"P6\n7 7\n255\n".len() + 7 * 7 * 3);
+    }
+
+    #[test]
+    fn test_generator() {
+        assert_eq!(
+            located(generator(
+                "Sensor at x=2, y=18: closest beacon is at x=-2, y=15\nSensor x=1"
+            )),
+            "2:1 expected a sensor report"
+        );
+        assert_eq!(
+            located(generator(
+                "Sensor at x=2, y=q: closest beacon is at x=-2, y=15"
+            )),
+            "1:18 expected a number"
+        );
     }
 }
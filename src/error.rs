@@ -0,0 +1,113 @@
+use std::fmt;
+
+/** malformed puzzle input, located by 1-based line & column */
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ParseError {
+    pub day: u8,
+    pub line: usize,
+    pub column: usize,
+    pub text: String,
+    pub source_line: String,
+    pub message: String,
+}
+impl ParseError {
+    /** `text` should be a slice of `input` so it can be located, otherwise it's reported at the end of input */
+    pub fn new(day: u8, input: &str, text: &str, message: impl Into<String>) -> Self {
+        let start = input.as_ptr() as usize;
+        let at = text.as_ptr() as usize;
+        let offset = if at >= start && at + text.len() <= start + input.len() {
+            at - start
+        } else {
+            input.len()
+        };
+
+        let line_start = input[..offset].rfind('\n').map_or(0, |i| i + 1);
+        let line_end = input[offset..]
+            .find('\n')
+            .map_or(input.len(), |i| offset + i);
+
+        Self {
+            day,
+            line: input[..offset].matches('\n').count() + 1,
+            column: input[line_start..offset].chars().count() + 1,
+            text: text.to_string(),
+            source_line: input[line_start..line_end]
+                .trim_end_matches('\r')
+                .to_string(),
+            message: message.into(),
+        }
+    }
+
+    /** for errors found after the input has been consumed */
+    pub fn eof(day: u8, input: &str, message: impl Into<String>) -> Self {
+        Self::new(day, input, &input[input.len()..], message)
+    }
+}
+
+impl fmt::Display for ParseError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let gutter = " ".repeat(self.line.to_string().len());
+        // only underline what's actually on this line
+        let width = self
+            .text
+            .lines()
+            .next()
+            .unwrap_or("")
+            .chars()
+            .count()
+            .max(1);
+
+        writeln!(
+            f,
+            "day {:02}, line {}, column {}: {}",
+            self.day, self.line, self.column, self.message
+        )?;
+        writeln!(f, "{} |", gutter)?;
+        writeln!(f, "{} | {}", self.line, self.source_line)?;
+        write!(
+            f,
+            "{} | {}{}",
+            gutter,
+            " ".repeat(self.column - 1),
+            "^".repeat(width)
+        )
+    }
+}
+
+impl std::error::Error for ParseError {}
+
+/** `line:column message` of a failed parse, for tests to assert where it went wrong */
+#[cfg(test)]
+pub fn located<T>(result: Result<T, ParseError>) -> String {
+    let error = result.err().expect("expected a parse error");
+    format!("{}:{} {}", error.line, error.column, error.message)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const SAMPLE: &str = "1-2,3-4
+5-x,7-9";
+
+    #[test]
+    fn test_location() {
+        let error = ParseError::new(4, SAMPLE, &SAMPLE[10..11], "expected a number");
+        assert_eq!((error.line, error.column), (2, 3));
+        assert_eq!(error.text, "x");
+        assert_eq!(error.source_line, "5-x,7-9");
+        assert_eq!(
+            error.to_string(),
+            "day 04, line 2, column 3: expected a number
+  |
+2 | 5-x,7-9
+  |   ^"
+        );
+    }
+
+    #[test]
+    fn test_eof() {
+        let error = ParseError::eof(4, SAMPLE, "unexpected end of input");
+        assert_eq!((error.line, error.column), (2, 8));
+    }
+}
@@ -0,0 +1,298 @@
+// shared by several days, not every helper is used by every puzzle
+#![allow(dead_code)]
+
+use std::{
+    cmp::Ordering,
+    ops::{Add, AddAssign, Neg, Sub},
+};
+
+use pathfinding::num_traits::{CheckedAdd, CheckedSub, One, Signed};
+
+/** x, y in screen coordinates, so y grows downward */
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub struct Point<T> {
+    pub x: T,
+    pub y: T,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Direction {
+    Up,
+    Down,
+    Left,
+    Right,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Diagonal {
+    UpLeft,
+    UpRight,
+    DownLeft,
+    DownRight,
+}
+
+impl Direction {
+    pub const ALL: [Direction; 4] = [
+        Direction::Up,
+        Direction::Down,
+        Direction::Left,
+        Direction::Right,
+    ];
+
+    /** x, y step as -1/0/1 */
+    pub fn delta(&self) -> (i8, i8) {
+        match self {
+            Direction::Up => (0, -1),
+            Direction::Down => (0, 1),
+            Direction::Left => (-1, 0),
+            Direction::Right => (1, 0),
+        }
+    }
+
+    pub fn offset<T: Signed + Copy>(&self) -> Point<T> {
+        Point::from_delta(self.delta())
+    }
+
+    pub fn opposite(&self) -> Self {
+        match self {
+            Direction::Up => Direction::Down,
+            Direction::Down => Direction::Up,
+            Direction::Left => Direction::Right,
+            Direction::Right => Direction::Left,
+        }
+    }
+
+    pub fn turn_right(&self) -> Self {
+        match self {
+            Direction::Up => Direction::Right,
+            Direction::Right => Direction::Down,
+            Direction::Down => Direction::Left,
+            Direction::Left => Direction::Up,
+        }
+    }
+
+    pub fn turn_left(&self) -> Self {
+        self.turn_right().opposite()
+    }
+
+    /** straight from one point toward another, None unless they share a row or column */
+    pub fn between<T: PartialOrd>(from: &Point<T>, to: &Point<T>) -> Option<Self> {
+        match (to.x.partial_cmp(&from.x)?, to.y.partial_cmp(&from.y)?) {
+            (Ordering::Equal, Ordering::Less) => Some(Direction::Up),
+            (Ordering::Equal, Ordering::Greater) => Some(Direction::Down),
+            (Ordering::Less, Ordering::Equal) => Some(Direction::Left),
+            (Ordering::Greater, Ordering::Equal) => Some(Direction::Right),
+            _ => None,
+        }
+    }
+
+    /** as drawn in puzzle text */
+    pub fn arrow(&self) -> char {
+        match self {
+            Direction::Up => '^',
+            Direction::Down => 'v',
+            Direction::Left => '<',
+            Direction::Right => '>',
+        }
+    }
+}
+
+impl Diagonal {
+    pub const ALL: [Diagonal; 4] = [
+        Diagonal::UpLeft,
+        Diagonal::UpRight,
+        Diagonal::DownLeft,
+        Diagonal::DownRight,
+    ];
+
+    pub fn delta(&self) -> (i8, i8) {
+        match self {
+            Diagonal::UpLeft => (-1, -1),
+            Diagonal::UpRight => (1, -1),
+            Diagonal::DownLeft => (-1, 1),
+            Diagonal::DownRight => (1, 1),
+        }
+    }
+
+    pub fn offset<T: Signed + Copy>(&self) -> Point<T> {
+        Point::from_delta(self.delta())
+    }
+}
+
+impl<T> Point<T> {
+    pub const fn new(x: T, y: T) -> Self {
+        Self { x, y }
+    }
+}
+
+impl<T: Copy + PartialOrd> Point<T> {
+    /** inclusive on both corners */
+    pub fn within(&self, min: &Point<T>, max: &Point<T>) -> bool {
+        self.x >= min.x && self.x <= max.x && self.y >= min.y && self.y <= max.y
+    }
+}
+
+impl<T: Copy + PartialOrd + Sub<Output = T>> Point<T> {
+    pub fn manhattan_distance(&self, other: &Point<T>) -> T
+    where
+        T: Add<Output = T>,
+    {
+        abs_diff(self.x, other.x) + abs_diff(self.y, other.y)
+    }
+
+    pub fn chebyshev_distance(&self, other: &Point<T>) -> T {
+        let (dx, dy) = (abs_diff(self.x, other.x), abs_diff(self.y, other.y));
+        if dx > dy {
+            dx
+        } else {
+            dy
+        }
+    }
+}
+
+impl<T: Copy + CheckedAdd + CheckedSub + One> Point<T> {
+    /** None when the step would leave the type's range, e.g. below 0 for usize */
+    pub fn step(&self, delta: (i8, i8)) -> Option<Self> {
+        Some(Self {
+            x: shift(self.x, delta.0)?,
+            y: shift(self.y, delta.1)?,
+        })
+    }
+
+    /** up, down, left, right */
+    pub fn neighbors4(&self) -> impl Iterator<Item = Self> + '_ {
+        Direction::ALL
+            .iter()
+            .filter_map(|direction| self.step(direction.delta()))
+    }
+
+    /** neighbors4 followed by the diagonals */
+    pub fn neighbors8(&self) -> impl Iterator<Item = Self> + '_ {
+        self.neighbors4().chain(
+            Diagonal::ALL
+                .iter()
+                .filter_map(|diagonal| self.step(diagonal.delta())),
+        )
+    }
+}
+
+impl<T: Signed + Copy> Point<T> {
+    fn from_delta(delta: (i8, i8)) -> Self {
+        let unit = |d: i8| match d {
+            -1 => -T::one(),
+            1 => T::one(),
+            _ => T::zero(),
+        };
+        Self {
+            x: unit(delta.0),
+            y: unit(delta.1),
+        }
+    }
+
+    /** each axis as -1/0/1 */
+    pub fn signum(&self) -> Self {
+        Self {
+            x: self.x.signum(),
+            y: self.y.signum(),
+        }
+    }
+}
+
+impl<T: Copy + Neg<Output = T>> Point<T> {
+    /** quarter turn clockwise (on screen) around the origin */
+    pub fn rotate_right(&self) -> Self {
+        Self {
+            x: -self.y,
+            y: self.x,
+        }
+    }
+
+    /** quarter turn counter clockwise (on screen) around the origin */
+    pub fn rotate_left(&self) -> Self {
+        Self {
+            x: self.y,
+            y: -self.x,
+        }
+    }
+}
+
+impl<T: Add<Output = T>> Add for Point<T> {
+    type Output = Self;
+
+    fn add(self, other: Self) -> Self {
+        Self {
+            x: self.x + other.x,
+            y: self.y + other.y,
+        }
+    }
+}
+
+impl<T: Add<Output = T> + Copy> AddAssign for Point<T> {
+    fn add_assign(&mut self, other: Self) {
+        *self = *self + other;
+    }
+}
+
+impl<T: Sub<Output = T>> Sub for Point<T> {
+    type Output = Self;
+
+    fn sub(self, other: Self) -> Self {
+        Self {
+            x: self.x - other.x,
+            y: self.y - other.y,
+        }
+    }
+}
+
+fn abs_diff<T: PartialOrd + Sub<Output = T>>(a: T, b: T) -> T {
+    if a > b {
+        a - b
+    } else {
+        b - a
+    }
+}
+
+fn shift<T: CheckedAdd + CheckedSub + One>(value: T, delta: i8) -> Option<T> {
+    match delta {
+        -1 => value.checked_sub(&T::one()),
+        1 => value.checked_add(&T::one()),
+        _ => Some(value),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_neighbors() {
+        let corner: Point<usize> = Point::new(0, 0);
+        assert_eq!(
+            corner.neighbors4().collect::<Vec<_>>(),
+            vec![Point::new(0, 1), Point::new(1, 0)]
+        );
+        assert_eq!(corner.neighbors8().count(), 3);
+        assert_eq!(Point::new(5i32, 5).neighbors8().count(), 8);
+    }
+
+    #[test]
+    fn test_distance_and_rotation() {
+        let a = Point::new(2i32, 18);
+        let b = Point::new(-2, 15);
+        assert_eq!(a.manhattan_distance(&b), 7);
+        assert_eq!(a.chebyshev_distance(&b), 4);
+        assert_eq!((a - b).signum(), Point::new(1, 1));
+
+        let right: Point<i32> = Direction::Right.offset();
+        assert_eq!(right.rotate_right(), Direction::Down.offset());
+        assert_eq!(right.rotate_left(), Direction::Up.offset());
+        assert_eq!(Direction::Up.turn_left(), Direction::Left);
+        assert_eq!(
+            Direction::between(&a, &Point::new(2, 3)),
+            Some(Direction::Up)
+        );
+        assert_eq!(Direction::between(&a, &b), None);
+        assert!(a.within(&Point::new(0, 0), &Point::new(20, 20)));
+        assert!(!b.within(&Point::new(0, 0), &Point::new(20, 20)));
+    }
+}
@@ -1,3 +1,4 @@
+mod bigint;
 mod day01;
 mod day02;
 mod day03;
@@ -13,22 +14,25 @@ mod day12;
 mod day13;
 mod day14;
 mod day15;
+mod error;
+mod geom;
+mod ocr;
 
 aoc_main::main! {
   year 2022;
-  day01 : generator => part1, part2;
-  day02 : generator => part1, part2;
-  day03 : generator => part1, part2;
-  day04 : generator => part1, part2;
-  day05 : generator => part1, part2;
-  day06 : generator => part1, part2;
-  day07 : generator => part1, part2;
-  day08 : generator => part1, part2;
-  day09 : generator => part1, part2;
-  day10 : generator => part1, part2;
-  day11 : generator => part1, part2;
-  day12 : generator => part1, part2;
-  day13 : generator => part1, part2;
-  day14 : generator => part1, part2;
-  day15 : generator => part1, part2;
+  day01 : generator? => part1, part2;
+  day02 : generator? => part1, part2;
+  day03 : generator? => part1, part2;
+  day04 : generator? => part1, part2;
+  day05 : generator? => part1, part2;
+  day06 : generator? => part1, part2;
+  day07 : generator? => part1, part2;
+  day08 : generator? => part1, part2;
+  day09 : generator? => part1, part2;
+  day10 : generator? => part1, part2, part2_ocr?;
+  day11 : generator? => part1?, part2?, part2_items?;
+  day12 : generator? => part1, part2, part2_field, part2_hike;
+  day13 : generator? => part1, part2;
+  day14 : generator? => part1, part2;
+  day15 : generator? => part1, part2, part2_lines;
 }
@@ -0,0 +1,153 @@
+use std::fmt;
+
+use itertools::Itertools;
+
+const WIDTH: usize = 4;
+const HEIGHT: usize = 6;
+/** glyphs are 4 pixels wide with a blank column after each */
+const PITCH: usize = WIDTH + 1;
+
+/** the letters AoC has drawn so far in its 4x6 font */
+const GLYPHS: [(char, &str); 16] = [
+    ('A', ".##.#..##..######..##..#"),
+    ('B', "###.#..####.#..##..####."),
+    ('C', ".##.#..##...#...#..#.##."),
+    ('E', "#####...###.#...#...####"),
+    ('F', "#####...###.#...#...#..."),
+    ('G', ".##.#..##...#.###..#.###"),
+    ('H', "#..##..######..##..##..#"),
+    ('J', "..##...#...#...##..#.##."),
+    ('K', "#..##.#.##..#.#.#.#.#..#"),
+    ('L', "#...#...#...#...#...####"),
+    ('O', ".##.#..##..##..##..#.##."),
+    ('P', "###.#..##..####.#...#..."),
+    ('R', "###.#..##..####.#.#.#..#"),
+    ('S', ".####...#....##....####."),
+    ('U', "#..##..##..##..##..#.##."),
+    ('Z', "####...#..#..#..#...####"),
+];
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum OcrError {
+    Glyph {
+        /** 0-based pixel column where the glyph starts */
+        column: usize,
+        glyph: String,
+    },
+    /** letters are drawn exactly `HEIGHT` rows tall */
+    Height { expected: usize, found: usize },
+}
+impl fmt::Display for OcrError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            OcrError::Glyph { column, glyph } => {
+                writeln!(f, "unrecognised glyph at column {}", column)?;
+                write!(
+                    f,
+                    "{}",
+                    glyph
+                        .chars()
+                        .chunks(WIDTH)
+                        .into_iter()
+                        .map(|row| row.collect::<String>())
+                        .join("\n")
+                )
+            }
+            OcrError::Height { expected, found } => write!(
+                f,
+                "expected a screen {} rows tall, found {}",
+                expected, found
+            ),
+        }
+    }
+}
+
+impl std::error::Error for OcrError {}
+
+/** read letters off a screen where `#` is lit and anything else is dark */
+pub fn decode(screen: &str) -> Result<String, OcrError> {
+    let rows = screen.lines().collect_vec();
+    if rows.len() != HEIGHT {
+        return Err(OcrError::Height {
+            expected: HEIGHT,
+            found: rows.len(),
+        });
+    }
+    let columns = rows
+        .iter()
+        .map(|row| row.chars().count())
+        .max()
+        .unwrap_or(0);
+
+    let letters = (0..columns)
+        .step_by(PITCH)
+        .map(|column| {
+            let glyph: String = rows
+                .iter()
+                .flat_map(|row| {
+                    let mut pixels = row.chars().skip(column).take(WIDTH).collect_vec();
+                    pixels.resize(WIDTH, '.');
+                    pixels
+                })
+                .map(|pixel| if pixel == '#' { '#' } else { '.' })
+                .collect();
+
+            if !glyph.contains('#') {
+                return Ok(' ');
+            }
+            GLYPHS
+                .iter()
+                .find(|(_, pattern)| *pattern == glyph)
+                .map(|(letter, _)| *letter)
+                .ok_or(OcrError::Glyph { column, glyph })
+        })
+        .collect::<Result<String, _>>()?;
+
+    Ok(letters.trim().to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const SAMPLE: &str = "####.#..#.###..####.###....##..##..#....
+#....#..#.#..#....#.#..#....#.#..#.#....
+###..####.#..#...#..#..#....#.#....#....
+#....#..#.###...#...###.....#.#.##.#....
+#....#..#.#....#....#....#..#.#..#.#....
+####.#..#.#....####.#.....##...###.####.";
+
+    #[test]
+    fn test_decode() {
+        assert_eq!(decode(SAMPLE).unwrap(), "EHPZPJGL");
+        assert_eq!(decode(&SAMPLE.replace('.', " ")).unwrap(), "EHPZPJGL");
+    }
+
+    #[test]
+    fn test_unrecognised() {
+        // light an extra pixel in the top left of the J
+        let mut garbled = SAMPLE.to_string();
+        garbled.replace_range(25..26, "#");
+        assert_eq!(
+            decode(&garbled),
+            Err(OcrError::Glyph {
+                column: 25,
+                glyph: "#.##...#...#...##..#.##.".to_string()
+            })
+        );
+    }
+
+    #[test]
+    fn test_height() {
+        let short = SAMPLE.lines().take(5).join("\n");
+        let error = decode(&short).unwrap_err();
+        assert_eq!(
+            error,
+            OcrError::Height {
+                expected: 6,
+                found: 5
+            }
+        );
+        assert_eq!(error.to_string(), "expected a screen 6 rows tall, found 5");
+    }
+}